@@ -0,0 +1,226 @@
+//! Deserialize typed values straight from a [`Jsona`] tree, so that errors can point at the
+//! node which caused them.
+
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use std::fmt;
+
+use crate::syntax::{Jsona, Position, Property};
+
+/// Deserialize `node`, on error returns the message and the position of the innermost
+/// node that failed.
+pub(crate) fn from_jsona<T: DeserializeOwned>(node: &Jsona) -> Result<T, (String, Position)> {
+    T::deserialize(Deserializer(node))
+        .map_err(|e| (e.message, e.position.unwrap_or(*node.get_position())))
+}
+
+#[derive(Debug)]
+struct Error {
+    message: String,
+    position: Option<Position>,
+}
+
+impl Error {
+    fn at(mut self, position: &Position) -> Self {
+        self.position.get_or_insert(*position);
+        self
+    }
+}
+
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error {
+            message: msg.to_string(),
+            position: None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+struct Deserializer<'a>(&'a Jsona);
+
+impl<'a> Deserializer<'a> {
+    fn locate<T>(&self, result: Result<T, Error>) -> Result<T, Error> {
+        result.map_err(|e| e.at(self.0.get_position()))
+    }
+}
+
+impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let result = match self.0 {
+            Jsona::Null(_) => visitor.visit_unit(),
+            Jsona::Boolean(v) => visitor.visit_bool(v.value),
+            Jsona::Integer(v) => visitor.visit_i64(v.value),
+            Jsona::Float(v) => visitor.visit_f64(v.value),
+            Jsona::String(v) => visitor.visit_borrowed_str(&v.value),
+            Jsona::Array(v) => {
+                let mut seq = Seq(v.elements.iter());
+                visitor
+                    .visit_seq(&mut seq)
+                    .and_then(|value| match seq.0.len() {
+                        0 => Ok(value),
+                        rest => Err(de::Error::invalid_length(
+                            v.elements.len(),
+                            &format!("{} elements", v.elements.len() - rest).as_str(),
+                        )),
+                    })
+            }
+            Jsona::Object(v) => visitor.visit_map(Map {
+                iter: v.properties.iter(),
+                value: None,
+            }),
+        };
+        self.locate(result)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let result = match self.0 {
+            Jsona::Null(_) => visitor.visit_none(),
+            _ => visitor.visit_some(Deserializer(self.0)),
+        };
+        self.locate(result)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let result = visitor.visit_newtype_struct(Deserializer(self.0));
+        self.locate(result)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let result = match self.0 {
+            Jsona::String(v) => visitor.visit_enum(v.value.as_str().into_deserializer()),
+            Jsona::Object(v) if v.properties.len() == 1 => {
+                visitor.visit_enum(Enum(&v.properties[0]))
+            }
+            _ => Err(de::Error::custom(
+                "expected a string or an object with a single key",
+            )),
+        };
+        self.locate(result)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+struct Seq<'a>(std::slice::Iter<'a, Jsona>);
+
+impl<'de> SeqAccess<'de> for Seq<'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.0
+            .next()
+            .map(|node| seed.deserialize(Deserializer(node)))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct Map<'a> {
+    iter: std::slice::Iter<'a, Property>,
+    value: Option<&'a Jsona>,
+}
+
+impl<'de> MapAccess<'de> for Map<'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.iter.next() {
+            Some(prop) => {
+                self.value = Some(&prop.value);
+                let key: de::value::BorrowedStrDeserializer<Error> =
+                    de::value::BorrowedStrDeserializer::new(&prop.key);
+                seed.deserialize(key)
+                    .map(Some)
+                    .map_err(|e| e.at(&prop.position))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| de::Error::custom("value is missing"))?;
+        seed.deserialize(Deserializer(value))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.iter.len())
+    }
+}
+
+struct Enum<'a>(&'a Property);
+
+impl<'de> EnumAccess<'de> for Enum<'de> {
+    type Error = Error;
+    type Variant = Deserializer<'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self::Variant), Error> {
+        let key: de::value::BorrowedStrDeserializer<Error> =
+            de::value::BorrowedStrDeserializer::new(&self.0.key);
+        let variant = seed.deserialize(key).map_err(|e| e.at(&self.0.position))?;
+        Ok((variant, Deserializer(&self.0.value)))
+    }
+}
+
+impl<'de> VariantAccess<'de> for Deserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
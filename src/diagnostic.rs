@@ -0,0 +1,180 @@
+//! Rendering of errors with the offending source lines, in the style of rustc.
+//!
+//! ```text
+//! error[E0001]: unexpected token 'eof'
+//!  --> main.jsona:3:1
+//!   |
+//! 1 | {
+//!   | - object opened here
+//! 2 |   x: 1,
+//! 3 |
+//!   | ^
+//! ```
+
+use std::io::IsTerminal;
+
+use crate::error::Error;
+use crate::lexer::{Lexer, TokenKind};
+use crate::source::{FileId, SourceMap};
+use crate::syntax::Position;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color if stderr is a terminal and `NO_COLOR` is not set.
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Renderer {
+    color: bool,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new(ColorChoice::Never)
+    }
+}
+
+impl Renderer {
+    pub fn new(color: ColorChoice) -> Self {
+        let color = match color {
+            ColorChoice::Auto => {
+                std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal()
+            }
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+        Self { color }
+    }
+    /// Render `error` against the files its positions refer to.
+    pub fn render(&self, sources: &SourceMap, error: &Error) -> String {
+        let mut output = format!(
+            "{}{}\n",
+            self.paint(RED, &format!("error[{}]", error.kind.code())),
+            self.paint(BOLD, &format!(": {}", error.info))
+        );
+        let file = error.position.file;
+        let mut marks = vec![(error.position, "", true)];
+        for label in &error.labels {
+            if label.position.file == file {
+                marks.push((label.position, &label.message, false));
+            }
+        }
+        marks.sort_by_key(|(position, _, _)| (position.line, position.col));
+        let width = marks
+            .iter()
+            .map(|(position, _, _)| position.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let gutter = |line: &str| self.paint(BLUE, &format!("{:>w$} |", line, w = width));
+        output.push_str(&format!(
+            "{:w$}{} {}\n",
+            "",
+            self.paint(BLUE, "-->"),
+            sources.location(&error.position),
+            w = width
+        ));
+        if let Some(source) = sources.get(file) {
+            output.push_str(&gutter(""));
+            output.push('\n');
+            let mut last_line = None;
+            for (position, message, primary) in &marks {
+                let text = source.line(position.line).unwrap_or("");
+                if last_line != Some(position.line) {
+                    if let Some(last) = last_line {
+                        if position.line > last + 1 {
+                            output.push_str(&self.paint(BLUE, "..."));
+                            output.push('\n');
+                        }
+                    }
+                    output.push_str(&gutter(&position.line.to_string()));
+                    if !text.is_empty() {
+                        output.push(' ');
+                        output.push_str(text);
+                    }
+                    output.push('\n');
+                    last_line = Some(position.line);
+                }
+                let (mark, color) = if *primary { ('^', RED) } else { ('-', BLUE) };
+                let marker = mark.to_string().repeat(token_width(text, position.col));
+                let mut line = format!(
+                    "{} {}{}",
+                    gutter(""),
+                    indent(text, position.col),
+                    self.paint(color, &marker)
+                );
+                if !message.is_empty() {
+                    line.push(' ');
+                    line.push_str(&self.paint(color, message));
+                }
+                output.push_str(&line);
+                output.push('\n');
+            }
+        }
+        for label in &error.labels {
+            if label.position.file != file {
+                output.push_str(&format!(
+                    "{:w$}{} {}: {}\n",
+                    "",
+                    self.paint(BLUE, "="),
+                    sources.location(&label.position),
+                    label.message,
+                    w = width
+                ));
+            }
+        }
+        output
+    }
+    /// Render `error` against `source`, for positions that do not refer to a file.
+    pub fn render_source(&self, path: &str, source: &str, error: &Error) -> String {
+        let mut sources = SourceMap::new();
+        let id = sources.add(path, source);
+        let mut error = error.clone();
+        set_file(&mut error.position, id);
+        for label in error.labels.iter_mut() {
+            set_file(&mut label.position, id);
+        }
+        self.render(&sources, &error)
+    }
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+fn set_file(position: &mut Position, file: FileId) {
+    if position.file.is_none() {
+        position.file = file;
+    }
+}
+
+/// Whitespace up to column `col` of `text`, keeping tabs so markers line up.
+fn indent(text: &str, col: usize) -> String {
+    text.chars()
+        .chain(std::iter::repeat(' '))
+        .take(col.saturating_sub(1))
+        .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+        .collect()
+}
+
+/// Width of the token starting at column `col` of `text`, at least 1.
+pub(crate) fn token_width(text: &str, col: usize) -> usize {
+    let rest: String = text.chars().skip(col.saturating_sub(1)).collect();
+    let mut lexer = Lexer::new(rest.chars());
+    match lexer.next() {
+        Some(tok) if tok.position.index == 0 && tok.kind != TokenKind::Eof => {
+            lexer.position().index.min(rest.chars().count()).max(1)
+        }
+        _ => 1,
+    }
+}
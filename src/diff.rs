@@ -0,0 +1,207 @@
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::pointer;
+use crate::syntax::{Annotation, Array, Float, Integer, Jsona, Object, Position, Property};
+
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Do not report objects whose properties only differ in order.
+    pub ignore_key_order: bool,
+    /// Numbers are equal if they differ by no more than this.
+    pub float_epsilon: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Changed,
+    /// The object has the same keys in a different order.
+    Reordered,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// JSON pointer of the node.
+    pub path: String,
+    /// Set if the change is about an annotation of the node rather than its value.
+    pub annotation: Option<String>,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+    pub old_position: Option<Position>,
+    pub new_position: Option<Position>,
+}
+
+/// Compare two documents, including annotations.
+pub fn diff(old: &Jsona, new: &Jsona, options: &DiffOptions) -> Vec<Change> {
+    let mut differ = Differ {
+        options,
+        path: Vec::new(),
+        changes: Vec::new(),
+    };
+    differ.diff_node(old, new);
+    differ.changes
+}
+
+struct Differ<'a> {
+    options: &'a DiffOptions,
+    path: Vec<String>,
+    changes: Vec<Change>,
+}
+
+impl<'a> Differ<'a> {
+    fn diff_node(&mut self, old: &Jsona, new: &Jsona) {
+        self.diff_annotations(old, new);
+        match (old, new) {
+            (Jsona::Array(Array { elements: a, .. }), Jsona::Array(Array { elements: b, .. })) => {
+                for (i, (a, b)) in a.iter().zip(b.iter()).enumerate() {
+                    self.path.push(i.to_string());
+                    self.diff_node(a, b);
+                    self.path.pop();
+                }
+                for (i, a) in a.iter().enumerate().skip(b.len()) {
+                    self.path.push(i.to_string());
+                    self.push_node(ChangeKind::Removed, Some(a), None);
+                    self.path.pop();
+                }
+                for (i, b) in b.iter().enumerate().skip(a.len()) {
+                    self.path.push(i.to_string());
+                    self.push_node(ChangeKind::Added, None, Some(b));
+                    self.path.pop();
+                }
+            }
+            (
+                Jsona::Object(Object { properties: a, .. }),
+                Jsona::Object(Object { properties: b, .. }),
+            ) => {
+                for prop in a.iter() {
+                    self.path.push(prop.key.clone());
+                    match b.iter().find(|p| p.key == prop.key) {
+                        Some(other) => self.diff_node(&prop.value, &other.value),
+                        None => self.push_node(ChangeKind::Removed, Some(&prop.value), None),
+                    }
+                    self.path.pop();
+                }
+                for prop in b.iter() {
+                    if !a.iter().any(|p| p.key == prop.key) {
+                        self.path.push(prop.key.clone());
+                        self.push_node(ChangeKind::Added, None, Some(&prop.value));
+                        self.path.pop();
+                    }
+                }
+                if !self.options.ignore_key_order {
+                    let common = |x: &[Property], y: &[Property]| {
+                        x.iter()
+                            .filter(|p| y.iter().any(|q| q.key == p.key))
+                            .map(|p| p.key.clone())
+                            .collect::<Vec<_>>()
+                    };
+                    if common(a, b) != common(b, a) {
+                        self.push_node(ChangeKind::Reordered, Some(old), Some(new));
+                    }
+                }
+            }
+            _ => {
+                if !self.scalar_eq(old, new) {
+                    self.push_node(ChangeKind::Changed, Some(old), Some(new));
+                }
+            }
+        }
+    }
+    fn diff_annotations(&mut self, old: &Jsona, new: &Jsona) {
+        let a = old.get_annotations();
+        let b = new.get_annotations();
+        // Annotations with the same name are paired up in order of appearance.
+        for (i, anno) in a.iter().enumerate() {
+            match nth_named(b, &anno.name, count_named(&a[..i], &anno.name)) {
+                Some(other) => {
+                    if !self.value_eq(&Value::from(&anno.value), &Value::from(&other.value)) {
+                        self.push_annotation(ChangeKind::Changed, Some(anno), Some(other));
+                    }
+                }
+                None => self.push_annotation(ChangeKind::Removed, Some(anno), None),
+            }
+        }
+        for (i, anno) in b.iter().enumerate() {
+            if nth_named(a, &anno.name, count_named(&b[..i], &anno.name)).is_none() {
+                self.push_annotation(ChangeKind::Added, None, Some(anno));
+            }
+        }
+    }
+    fn scalar_eq(&self, a: &Jsona, b: &Jsona) -> bool {
+        match (a, b) {
+            (Jsona::Null(..), Jsona::Null(..)) => true,
+            (Jsona::Boolean(a), Jsona::Boolean(b)) => a.value == b.value,
+            (Jsona::String(a), Jsona::String(b)) => a.value == b.value,
+            (Jsona::Integer(a), Jsona::Integer(b)) => a.value == b.value,
+            _ => match (as_f64(a), as_f64(b)) {
+                (Some(a), Some(b)) => self.float_eq(a, b),
+                _ => false,
+            },
+        }
+    }
+    fn value_eq(&self, a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Number(a), Value::Number(b)) if a.is_f64() || b.is_f64() => {
+                self.float_eq(a.as_f64().unwrap(), b.as_f64().unwrap())
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| self.value_eq(a, b))
+            }
+            (Value::Object(a), Value::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .all(|(k, v)| b.get(k).map(|w| self.value_eq(v, w)).unwrap_or(false))
+            }
+            _ => a == b,
+        }
+    }
+    fn float_eq(&self, a: f64, b: f64) -> bool {
+        a == b || (a - b).abs() <= self.options.float_epsilon
+    }
+    fn push_node(&mut self, kind: ChangeKind, old: Option<&Jsona>, new: Option<&Jsona>) {
+        self.changes.push(Change {
+            kind,
+            path: pointer::join(&self.path),
+            annotation: None,
+            old: old.map(Value::from),
+            new: new.map(Value::from),
+            old_position: old.map(|v| *v.get_position()),
+            new_position: new.map(|v| *v.get_position()),
+        })
+    }
+    fn push_annotation(
+        &mut self,
+        kind: ChangeKind,
+        old: Option<&Annotation>,
+        new: Option<&Annotation>,
+    ) {
+        self.changes.push(Change {
+            kind,
+            path: pointer::join(&self.path),
+            annotation: old.or(new).map(|v| v.name.clone()),
+            old: old.map(|v| Value::from(&v.value)),
+            new: new.map(|v| Value::from(&v.value)),
+            old_position: old.map(|v| v.position),
+            new_position: new.map(|v| v.position),
+        })
+    }
+}
+
+fn count_named(annotations: &[Annotation], name: &str) -> usize {
+    annotations.iter().filter(|v| v.name == name).count()
+}
+
+fn nth_named<'a>(annotations: &'a [Annotation], name: &str, n: usize) -> Option<&'a Annotation> {
+    annotations.iter().filter(|v| v.name == name).nth(n)
+}
+
+fn as_f64(node: &Jsona) -> Option<f64> {
+    match node {
+        Jsona::Integer(Integer { value, .. }) => Some(*value as f64),
+        Jsona::Float(Float { value, .. }) => Some(*value),
+        _ => None,
+    }
+}
@@ -0,0 +1,259 @@
+//! Formatting of `Jsona` trees back to JSONA text.
+//!
+//! Annotations of a container follow its opening bracket, annotations of other nodes follow the
+//! comma after them. Annotations of a scalar root are not emitted, JSONA can not express them.
+//!
+//! Comments are not part of the tree, [`emit_with_comments`] takes them from the source the
+//! tree was parsed from. A comment on a line of its own stays on a line of its own, any other
+//! comment follows the item before it.
+
+use crate::lexer::{Lexer, TokenKind};
+use crate::syntax::{Annotation, Array, Boolean, Float, Integer, Jsona, Object, String};
+use std::string;
+
+#[derive(Debug, Clone)]
+pub struct EmitterOptions {
+    /// Spaces per nesting level.
+    pub indent: usize,
+}
+
+impl Default for EmitterOptions {
+    fn default() -> Self {
+        Self { indent: 4 }
+    }
+}
+
+/// Format `doc` as JSONA text, ending with a newline.
+pub fn emit(doc: &Jsona, options: &EmitterOptions) -> string::String {
+    Emitter::new(options, &[]).emit(doc)
+}
+
+/// Format `doc` keeping the comments of `source`, which `doc` must be parsed from.
+pub fn emit_with_comments(doc: &Jsona, source: &str, options: &EmitterOptions) -> string::String {
+    Emitter::new(options, &collect_comments(source)).emit(doc)
+}
+
+struct Comment {
+    index: usize,
+    text: string::String,
+    /// Only whitespace precedes the comment on its line.
+    own_line: bool,
+}
+
+fn collect_comments(source: &str) -> Vec<Comment> {
+    let chars: Vec<char> = source.chars().collect();
+    Lexer::new(source.chars())
+        .with_comments(true)
+        .filter_map(|tok| match tok.kind {
+            TokenKind::Comment(text) => {
+                let index = tok.position.index;
+                let own_line = chars[..index]
+                    .iter()
+                    .rev()
+                    .take_while(|&&ch| ch != '\n')
+                    .all(|ch| ch.is_whitespace());
+                Some(Comment {
+                    index,
+                    text,
+                    own_line,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+struct Emitter<'a> {
+    options: &'a EmitterOptions,
+    output: string::String,
+    level: usize,
+    comments: &'a [Comment],
+    /// The current line ends with a `//` comment.
+    in_line_comment: bool,
+}
+
+impl<'a> Emitter<'a> {
+    fn new(options: &'a EmitterOptions, comments: &'a [Comment]) -> Self {
+        Self {
+            options,
+            output: string::String::new(),
+            level: 0,
+            comments,
+            in_line_comment: false,
+        }
+    }
+    fn emit(mut self, doc: &Jsona) -> string::String {
+        let start = doc.get_position().index;
+        while let Some(comment) = self.comments.first().filter(|c| c.index < start) {
+            self.output.push_str(&comment.text);
+            self.output.push('\n');
+            self.comments = &self.comments[1..];
+        }
+        self.emit_node(doc);
+        self.emit_comments(usize::MAX);
+        self.output.push('\n');
+        self.output
+    }
+    fn emit_node(&mut self, node: &Jsona) {
+        match node {
+            Jsona::Array(Array {
+                elements,
+                annotations,
+                end,
+                ..
+            }) => {
+                let starts: Vec<_> = elements.iter().map(|v| v.get_position().index).collect();
+                self.output.push('[');
+                self.emit_container(annotations, &starts, end.index, ']', |emitter, i| {
+                    let elem = &elements[i];
+                    emitter.emit_node(elem);
+                    elem
+                });
+            }
+            Jsona::Object(Object {
+                properties,
+                annotations,
+                end,
+                ..
+            }) => {
+                let starts: Vec<_> = properties.iter().map(|p| p.position.index).collect();
+                self.output.push('{');
+                self.emit_container(annotations, &starts, end.index, '}', |emitter, i| {
+                    let prop = &properties[i];
+                    emitter.output.push_str(&format_key(&prop.key));
+                    emitter.output.push_str(": ");
+                    emitter.emit_node(&prop.value);
+                    &prop.value
+                });
+            }
+            _ => self.output.push_str(&format_scalar(node)),
+        }
+    }
+    /// Emit the annotations and items of a container after its opening bracket, `starts`
+    /// and `end` are the source indexes of the items and of the container end.
+    fn emit_container<'n, F>(
+        &mut self,
+        annotations: &[Annotation],
+        starts: &[usize],
+        end: usize,
+        close: char,
+        mut emit_item: F,
+    ) where
+        F: FnMut(&mut Self, usize) -> &'n Jsona,
+    {
+        let has_comments = matches!(self.comments.first(), Some(c) if c.index < end);
+        if starts.is_empty() && annotations.is_empty() && !has_comments {
+            self.output.push(close);
+            return;
+        }
+        self.emit_annotations(annotations);
+        self.level += 1;
+        for (i, start) in starts.iter().enumerate() {
+            self.emit_comments(*start);
+            self.newline();
+            let item = emit_item(self, i);
+            self.output.push(',');
+            if !item.is_array() && !item.is_object() {
+                self.emit_annotations(item.get_annotations());
+            }
+        }
+        self.emit_comments(end);
+        self.level -= 1;
+        self.newline();
+        self.output.push(close);
+    }
+    /// Emit the comments before the source index `before`.
+    fn emit_comments(&mut self, before: usize) {
+        while let Some(comment) = self.comments.first().filter(|c| c.index < before) {
+            if comment.own_line || self.in_line_comment {
+                self.newline();
+            } else {
+                self.output.push(' ');
+            }
+            self.output.push_str(&comment.text);
+            self.in_line_comment = comment.text.starts_with("//");
+            self.comments = &self.comments[1..];
+        }
+    }
+    fn emit_annotations(&mut self, annotations: &[Annotation]) {
+        for anno in annotations {
+            self.output.push_str(" @");
+            self.output.push_str(&anno.name);
+            if !anno.value.is_null() {
+                self.output.push('(');
+                self.output.push_str(&format_inline(&anno.value));
+                self.output.push(')');
+            }
+        }
+    }
+    fn newline(&mut self) {
+        self.in_line_comment = false;
+        self.output.push('\n');
+        let width = self.level * self.options.indent;
+        self.output.push_str(&" ".repeat(width));
+    }
+}
+
+/// Format a node on a single line, without annotations.
+pub fn format_inline(node: &Jsona) -> string::String {
+    match node {
+        Jsona::Array(Array { elements, .. }) => {
+            let elements: Vec<_> = elements.iter().map(format_inline).collect();
+            format!("[{}]", elements.join(", "))
+        }
+        Jsona::Object(Object { properties, .. }) => {
+            if properties.is_empty() {
+                return "{}".into();
+            }
+            let properties: Vec<_> = properties
+                .iter()
+                .map(|p| format!("{}: {}", format_key(&p.key), format_inline(&p.value)))
+                .collect();
+            format!("{{ {} }}", properties.join(", "))
+        }
+        _ => format_scalar(node),
+    }
+}
+
+fn format_scalar(node: &Jsona) -> string::String {
+    match node {
+        Jsona::Boolean(Boolean { value, .. }) => value.to_string(),
+        Jsona::Integer(Integer { value, .. }) => value.to_string(),
+        Jsona::Float(Float { value, .. }) => format_float(*value),
+        Jsona::String(String { value, .. }) => format_string(value),
+        _ => "null".into(),
+    }
+}
+
+/// Floats keep a fractional part so they read back as floats; JSONA has no exponent syntax.
+fn format_float(value: f64) -> string::String {
+    if !value.is_finite() {
+        return "null".into();
+    }
+    let text = value.to_string();
+    if text.contains('.') {
+        text
+    } else {
+        text + ".0"
+    }
+}
+
+fn format_string(value: &str) -> string::String {
+    serde_json::to_string(value).unwrap()
+}
+
+fn format_key(key: &str) -> string::String {
+    let mut chars = key.chars();
+    let is_identifier = match chars.next() {
+        Some(ch) => {
+            (ch.is_ascii_alphabetic() || ch == '_')
+                && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_')
+        }
+        None => false,
+    };
+    if is_identifier {
+        key.to_string()
+    } else {
+        format_string(key)
+    }
+}
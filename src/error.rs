@@ -3,15 +3,92 @@ use crate::lexer::{Token, TokenKind};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Error {
+    #[serde(default)]
+    pub kind: ErrorKind,
     pub info: String,
     pub position: Position,
+    /// Related places, such as where an unclosed object was opened.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<Label>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Label {
+    pub position: Position,
+    pub message: String,
+}
+
+/// What went wrong, for callers that react differently to different errors.
+///
+/// Each kind has a stable [code](ErrorKind::code) that is kept across releases.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum ErrorKind {
+    /// A token that is not allowed here, `expected` lists the allowed ones if known.
+    UnexpectedToken {
+        expected: Vec<String>,
+    },
+    UnexpectedCharacter,
+    UnterminatedString,
+    UnterminatedComment,
+    InvalidNumber,
+    InvalidEscape,
+    /// A limit such as the nesting depth was exceeded.
+    LimitExceeded,
+    /// An annotation has an argument of the wrong shape.
+    InvalidAnnotation,
+    /// An undefined, duplicated or cyclic `@save`/`@use` reference.
+    Reference,
+    /// A file can not be included.
+    Include,
+    /// A patch can not be applied.
+    Patch,
+    /// An annotation missing from the `AnnotationRegistry` in use.
+    UnknownAnnotation,
+    #[default]
+    Other,
+}
+
+impl ErrorKind {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::UnexpectedToken { .. } => "E0001",
+            ErrorKind::UnexpectedCharacter => "E0002",
+            ErrorKind::UnterminatedString => "E0003",
+            ErrorKind::UnterminatedComment => "E0004",
+            ErrorKind::InvalidNumber => "E0005",
+            ErrorKind::InvalidEscape => "E0006",
+            ErrorKind::LimitExceeded => "E0007",
+            ErrorKind::InvalidAnnotation => "E0101",
+            ErrorKind::Reference => "E0102",
+            ErrorKind::Include => "E0103",
+            ErrorKind::Patch => "E0104",
+            ErrorKind::UnknownAnnotation => "E0105",
+            ErrorKind::Other => "E0000",
+        }
+    }
 }
 
 impl Error {
     pub fn new(info: String, position: Position) -> Self {
-        Self { info, position }
+        Self {
+            kind: ErrorKind::Other,
+            info,
+            position,
+            labels: Vec::new(),
+        }
+    }
+    pub fn with_kind(mut self, kind: ErrorKind) -> Self {
+        self.kind = kind;
+        self
+    }
+    pub fn with_label(mut self, position: Position, message: &str) -> Self {
+        self.labels.push(Label {
+            position,
+            message: message.to_string(),
+        });
+        self
     }
     pub fn expect(expect_toks: &[TokenKind], tok: Token, context: String) -> Self {
         let info = format!(
@@ -46,20 +123,20 @@ impl Error {
             tok,
             context,
         );
-        Self::new(info, tok.position)
+        let expected = expect_toks.iter().map(TokenKind::to_string).collect();
+        Self::new(info, tok.position).with_kind(ErrorKind::UnexpectedToken { expected })
     }
     pub fn unexpect(tok: Token, context: Option<String>) -> Self {
         let info = match context {
             Some(ctx) => format!("unexpected token '{}' in {}", tok, ctx),
             None => format!("unexpected token '{}'", tok,),
         };
-        Self::new(info, tok.position)
+        Self::new(info, tok.position).with_kind(ErrorKind::UnexpectedToken {
+            expected: Vec::new(),
+        })
     }
     pub fn abort() -> Self {
-        Self {
-            info: String::from("abort"),
-            position: Position::default(),
-        }
+        Self::new(String::from("abort"), Position::default())
     }
 }
 
@@ -76,3 +153,5 @@ impl fmt::Display for Error {
         }
     }
 }
+
+impl std::error::Error for Error {}
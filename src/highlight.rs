@@ -0,0 +1,195 @@
+//! Syntax highlighting: classification of source spans and HTML/ANSI rendering.
+
+use crate::lexer::{Lexer, Token, TokenKind};
+use crate::syntax::Position;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Key,
+    String,
+    Number,
+    /// `true`, `false`, `null` and other bare identifiers in value position.
+    Keyword,
+    /// The `@` and name of an annotation.
+    AnnotationName,
+    /// Tokens inside the parentheses of an annotation.
+    AnnotationArgument,
+    Comment,
+    Punctuation,
+}
+
+impl TokenClass {
+    /// A stable name, used as CSS class suffix.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenClass::Key => "key",
+            TokenClass::String => "string",
+            TokenClass::Number => "number",
+            TokenClass::Keyword => "keyword",
+            TokenClass::AnnotationName => "annotation-name",
+            TokenClass::AnnotationArgument => "annotation-argument",
+            TokenClass::Comment => "comment",
+            TokenClass::Punctuation => "punctuation",
+        }
+    }
+    fn ansi(&self) -> Option<&'static str> {
+        match self {
+            TokenClass::Key => Some("34"),
+            TokenClass::String => Some("32"),
+            TokenClass::Number => Some("36"),
+            TokenClass::Keyword => Some("35"),
+            TokenClass::AnnotationName => Some("1;33"),
+            TokenClass::AnnotationArgument => Some("33"),
+            TokenClass::Comment => Some("90"),
+            TokenClass::Punctuation => None,
+        }
+    }
+}
+
+/// A classified span of the source, `[start, end)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub class: TokenClass,
+    pub start: Position,
+    pub end: Position,
+}
+
+/// Classify the tokens of `input`. Text that does not lex, and whitespace, is not covered.
+pub fn classify(input: &str) -> Vec<Span> {
+    let mut lexer = Lexer::new(input.chars()).with_comments(true);
+    let mut tokens: Vec<(Token, Position)> = Vec::new();
+    while let Some(tok) = lexer.next() {
+        match tok.kind {
+            TokenKind::Eof => break,
+            TokenKind::LexError(..) => continue,
+            _ => tokens.push((tok, lexer.position())),
+        }
+    }
+    let next_kind = |i: usize| {
+        tokens[i + 1..]
+            .iter()
+            .map(|(tok, _)| &tok.kind)
+            .find(|kind| !matches!(kind, TokenKind::Comment(..)))
+    };
+    let mut spans = Vec::new();
+    // Open brackets, to tell keys of objects from values.
+    let mut brackets = Vec::new();
+    // Parenthesis depth inside an annotation argument.
+    let mut argument_depth = 0;
+    // The previous token, ignoring comments, was `@` or an annotation name.
+    let mut after_at = false;
+    let mut after_name = false;
+    for (i, (tok, end)) in tokens.iter().enumerate() {
+        let class = match &tok.kind {
+            TokenKind::Comment(..) => TokenClass::Comment,
+            TokenKind::At if argument_depth == 0 => TokenClass::AnnotationName,
+            TokenKind::Identifier(..) if after_at => TokenClass::AnnotationName,
+            TokenKind::LeftParen if argument_depth > 0 || after_name => {
+                argument_depth += 1;
+                if argument_depth == 1 {
+                    TokenClass::Punctuation
+                } else {
+                    TokenClass::AnnotationArgument
+                }
+            }
+            TokenKind::RightParen if argument_depth > 0 => {
+                argument_depth -= 1;
+                if argument_depth == 0 {
+                    TokenClass::Punctuation
+                } else {
+                    TokenClass::AnnotationArgument
+                }
+            }
+            _ if argument_depth > 0 => TokenClass::AnnotationArgument,
+            TokenKind::LeftBrace | TokenKind::LeftBracket => {
+                brackets.push(tok.kind.clone());
+                TokenClass::Punctuation
+            }
+            TokenKind::RightBrace | TokenKind::RightBracket => {
+                brackets.pop();
+                TokenClass::Punctuation
+            }
+            TokenKind::Identifier(..)
+            | TokenKind::StringLiteral(..)
+            | TokenKind::IntegerLiteral(..)
+                if brackets.last() == Some(&TokenKind::LeftBrace)
+                    && next_kind(i) == Some(&TokenKind::Colon) =>
+            {
+                TokenClass::Key
+            }
+            TokenKind::Identifier(..) => TokenClass::Keyword,
+            TokenKind::StringLiteral(..) => TokenClass::String,
+            TokenKind::IntegerLiteral(..) | TokenKind::FloatLiteral(..) => TokenClass::Number,
+            _ => TokenClass::Punctuation,
+        };
+        if class != TokenClass::Comment {
+            after_name = after_at;
+            after_at = class == TokenClass::AnnotationName && tok.kind == TokenKind::At;
+        }
+        spans.push(Span {
+            class,
+            start: tok.position,
+            end: *end,
+        });
+    }
+    spans
+}
+
+/// Render `input` as an HTML code listing, spans get a `jsona-<class>` CSS class.
+pub fn to_html(input: &str) -> String {
+    let mut output = String::from("<pre class=\"jsona\"><code>");
+    render(input, |class, text| match class {
+        Some(class) => {
+            output.push_str(&format!("<span class=\"jsona-{}\">", class.as_str()));
+            output.push_str(&escape_html(text));
+            output.push_str("</span>");
+        }
+        None => output.push_str(&escape_html(text)),
+    });
+    output.push_str("</code></pre>");
+    output
+}
+
+/// Render `input` with ANSI terminal colors.
+pub fn to_ansi(input: &str) -> String {
+    let mut output = String::new();
+    render(input, |class, text| match class.and_then(|v| v.ansi()) {
+        Some(color) => output.push_str(&format!("\x1b[{}m{}\x1b[0m", color, text)),
+        None => output.push_str(text),
+    });
+    output
+}
+
+/// Call `f` with consecutive pieces of `input` and their class, if any.
+fn render<F: FnMut(Option<TokenClass>, &str)>(input: &str, mut f: F) {
+    let chars: Vec<char> = input.chars().collect();
+    let text = |start: usize, end: usize| chars[start..end].iter().collect::<String>();
+    let mut index = 0;
+    for span in classify(input) {
+        if span.start.index < index {
+            continue;
+        }
+        if span.start.index > index {
+            f(None, &text(index, span.start.index));
+        }
+        f(Some(span.class), &text(span.start.index, span.end.index));
+        index = span.end.index;
+    }
+    if index < chars.len() {
+        f(None, &text(index, chars.len()));
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            _ => output.push(ch),
+        }
+    }
+    output
+}
@@ -0,0 +1,244 @@
+//! Composition of documents split across files with `@include("path")`.
+//!
+//! A node annotated with `@include("path")` is replaced by the root of the included document,
+//! keeping the other annotations of the include site. Paths are relative to the including file.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::error::{Error, ErrorKind};
+use crate::source::SourceMap;
+use crate::syntax::{Array, Jsona, Object, Position};
+
+pub const INCLUDE: &str = "include";
+
+/// Where the sources of included files come from.
+pub trait SourceResolver {
+    /// Load the source of `path`, a path returned by [`SourceResolver::join`] or the entry path.
+    fn load(&self, path: &str) -> Result<String, String>;
+    /// Resolve `include` against the including file `from`.
+    fn join(&self, from: &str, include: &str) -> String {
+        join_path(from, include)
+    }
+}
+
+/// Load files below a root directory.
+#[derive(Debug, Clone)]
+pub struct FsResolver {
+    root: PathBuf,
+}
+
+impl FsResolver {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl SourceResolver for FsResolver {
+    fn load(&self, path: &str) -> Result<String, String> {
+        fs::read_to_string(self.root.join(path)).map_err(|e| e.to_string())
+    }
+}
+
+/// Load files from memory, e.g. sources embedded with `include_str!`.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    files: HashMap<String, String>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn insert(&mut self, path: &str, source: &str) {
+        self.files.insert(join_path("", path), source.to_string());
+    }
+}
+
+impl<'a> std::iter::FromIterator<(&'a str, &'a str)> for MemoryResolver {
+    fn from_iter<I: IntoIterator<Item = (&'a str, &'a str)>>(iter: I) -> Self {
+        let mut resolver = Self::new();
+        for (path, source) in iter {
+            resolver.insert(path, source);
+        }
+        resolver
+    }
+}
+
+impl SourceResolver for MemoryResolver {
+    fn load(&self, path: &str) -> Result<String, String> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| "file not found".to_string())
+    }
+}
+
+/// Resolve `include` relative to the directory of `from`, normalizing `.` and `..`.
+pub fn join_path(from: &str, include: &str) -> String {
+    let mut parts: Vec<&str> = if include.starts_with('/') {
+        Vec::new()
+    } else {
+        from.split('/').collect()
+    };
+    parts.pop();
+    for part in include.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop();
+            }
+            _ => parts.push(part),
+        }
+    }
+    parts.retain(|v| !v.is_empty());
+    parts.join("/")
+}
+
+/// An error in one file of a composed document.
+#[derive(Debug)]
+pub struct IncludeError {
+    /// The file the error is in.
+    pub file: String,
+    pub error: Error,
+    /// The include sites leading to `file`, innermost first.
+    pub stack: Vec<(String, Position)>,
+}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} in {}", self.error, self.file)?;
+        for (file, position) in &self.stack {
+            write!(
+                formatter,
+                "\n  included from {} at line {} column {}",
+                file, position.line, position.col
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for IncludeError {}
+
+/// A document composed from several files.
+#[derive(Debug, Clone)]
+pub struct Composed {
+    /// Positions in the tree refer to files in `sources`.
+    pub root: Jsona,
+    /// The files the document is made of, the entry file first.
+    pub sources: SourceMap,
+}
+
+impl Composed {
+    /// The file the node at the JSON pointer `path` came from.
+    pub fn file_of(&self, path: &str) -> Option<&str> {
+        let node = self.root.pointer(path)?;
+        self.sources
+            .get(node.get_position().file)
+            .map(|v| v.path.as_str())
+    }
+}
+
+/// Load `entry` and everything it includes.
+pub fn load(entry: &str, resolver: &dyn SourceResolver) -> Result<Composed, Box<IncludeError>> {
+    let mut includer = Includer {
+        resolver,
+        sources: SourceMap::new(),
+        stack: Vec::new(),
+    };
+    let entry = join_path("", entry);
+    let root = includer.load_file(&entry)?;
+    Ok(Composed {
+        root,
+        sources: includer.sources,
+    })
+}
+
+struct Includer<'a> {
+    resolver: &'a dyn SourceResolver,
+    sources: SourceMap,
+    /// Files being loaded with the position of the include site in each of them.
+    stack: Vec<(String, Position)>,
+}
+
+impl<'a> Includer<'a> {
+    fn load_file(&mut self, file: &str) -> Result<Jsona, Box<IncludeError>> {
+        let source = self
+            .resolver
+            .load(file)
+            .map_err(|e| self.error_at_site(file, format!("cannot load '{}', {}", file, e)))?;
+        let id = self.sources.add(file, &source);
+        let mut node = self.sources.parse(id).map_err(|e| self.error(file, e))?;
+        self.expand(&mut node, file)?;
+        Ok(node)
+    }
+    fn expand(&mut self, node: &mut Jsona, file: &str) -> Result<(), Box<IncludeError>> {
+        if let Some(anno) = node.annotation(INCLUDE).cloned() {
+            let include = match anno.value.as_string() {
+                Some(v) => self.resolver.join(file, &v.value),
+                None => {
+                    let info = format!("annotation '@{}' expects a path", INCLUDE);
+                    let error =
+                        Error::new(info, anno.position).with_kind(ErrorKind::InvalidAnnotation);
+                    return Err(self.error(file, error));
+                }
+            };
+            self.stack.push((file.to_string(), anno.position));
+            let mut files: Vec<&str> = self.stack.iter().map(|(v, _)| v.as_str()).collect();
+            if files.contains(&include.as_str()) {
+                files.push(&include);
+                let info = format!("include cycle {}", files.join(" -> "));
+                self.stack.pop();
+                let error = Error::new(info, anno.position).with_kind(ErrorKind::Include);
+                return Err(self.error(file, error));
+            }
+            let mut value = self.load_file(&include)?;
+            self.stack.pop();
+            let annotations = std::mem::take(node.get_annotations_mut());
+            value
+                .get_annotations_mut()
+                .extend(annotations.into_iter().filter(|v| v.name != INCLUDE));
+            *node = value;
+            return Ok(());
+        }
+        match node {
+            Jsona::Array(Array { elements, .. }) => {
+                for elem in elements.iter_mut() {
+                    self.expand(elem, file)?;
+                }
+            }
+            Jsona::Object(Object { properties, .. }) => {
+                for prop in properties.iter_mut() {
+                    self.expand(&mut prop.value, file)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+    fn error(&self, file: &str, error: Error) -> Box<IncludeError> {
+        Box::new(IncludeError {
+            file: file.to_string(),
+            error,
+            stack: self.stack.iter().rev().cloned().collect(),
+        })
+    }
+    /// An error at the include site of `file`, e.g. `file` can not be loaded.
+    fn error_at_site(&self, file: &str, info: String) -> Box<IncludeError> {
+        Box::new(match self.stack.split_last() {
+            Some(((file, position), rest)) => IncludeError {
+                file: file.clone(),
+                error: Error::new(info, *position).with_kind(ErrorKind::Include),
+                stack: rest.iter().rev().cloned().collect(),
+            },
+            None => IncludeError {
+                file: file.to_string(),
+                error: Error::new(info, Position::default()).with_kind(ErrorKind::Include),
+                stack: Vec::new(),
+            },
+        })
+    }
+}
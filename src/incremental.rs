@@ -0,0 +1,214 @@
+//! Reparsing of a document after a text edit.
+//!
+//! An edit strictly inside the brackets of an array or object only needs that container parsed
+//! again, provided its new text still forms exactly one container; the rest of the tree is kept
+//! and the positions after the edit are shifted. Other edits fall back to a full parse. Either
+//! way the tree is the one a full parse of the new text would give.
+//!
+//! The text is edited in place and only the nodes around and after the edit are visited, but
+//! finding the edit still scans the text up to the end of the container, and the fallback
+//! parse takes time linear in the document.
+
+use crate::error::Error;
+use crate::loader::Loader;
+use crate::syntax::{Array, Jsona, Object, Position};
+
+/// Replacement of the characters `[start, end)` of a text, counted in chars.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+impl TextEdit {
+    pub fn new(start: usize, end: usize, text: &str) -> Self {
+        Self {
+            start,
+            end,
+            text: text.to_string(),
+        }
+    }
+    /// The text after the edit. The range is clamped to `source`.
+    pub fn apply(&self, source: &str) -> String {
+        let (start, end) = self.range(source.chars().count());
+        let mut output: String = source.chars().take(start).collect();
+        output.push_str(&self.text);
+        output.extend(source.chars().skip(end));
+        output
+    }
+    fn range(&self, len: usize) -> (usize, usize) {
+        let end = self.end.min(len);
+        (self.start.min(end), end)
+    }
+}
+
+/// Apply `edit` to `source` and bring `doc`, the tree of `source`, up to date.
+///
+/// The edit is always applied; on a parse error `doc` is left as it was.
+pub fn reparse(doc: &mut Jsona, source: &mut String, edit: &TextEdit) -> Result<(), Error> {
+    let (start, end) = (edit.start.min(edit.end), edit.end);
+    let path = find_container(doc, start, end);
+    let (open, close) = match &path {
+        Some(path) => {
+            let container = node_at(doc, path);
+            (
+                container.get_position().index,
+                container.get_end_position().index,
+            )
+        }
+        None => (start, end),
+    };
+    let offsets = locate(source, &[open, start, end, close]);
+    let old_end = offsets[2].1;
+    let mut new_end = offsets[1].1;
+    edit.text.chars().for_each(|ch| advance(&mut new_end, ch));
+    let (open, start, end, close) = (offsets[0].0, offsets[1].0, offsets[2].0, offsets[3].0);
+    source.replace_range(start..end, &edit.text);
+    if let Some(path) = path {
+        let text = &source[open..start + edit.text.len() + (close - end)];
+        if let Some(mut node) = reparse_container(node_at(doc, &path), text) {
+            shift_after(doc, &old_end, &new_end);
+            let target = node_at_mut(doc, &path);
+            // Annotations after the closing bracket belong to the parent's text.
+            let close = target.get_end_position().index;
+            node.get_annotations_mut().extend(
+                target
+                    .get_annotations_mut()
+                    .drain(..)
+                    .filter(|anno| anno.position.index >= close),
+            );
+            *target = node;
+            return Ok(());
+        }
+    }
+    *doc = Loader::load_from_str(source)?;
+    Ok(())
+}
+
+/// Parse `text`, the new text of `node`, a container the edit lies within.
+fn reparse_container(node: &Jsona, text: &str) -> Option<Jsona> {
+    let start = *node.get_position();
+    let mut new_node = Loader::load_from_str(text).ok()?;
+    // A line comment may have swallowed the closing bracket along with text after it.
+    if new_node.get_end_position().index != text.chars().count() {
+        return None;
+    }
+    new_node.for_each_position_mut(&mut |position| {
+        if position.line == 1 {
+            position.col += start.col - 1;
+        }
+        position.line += start.line - 1;
+        position.index += start.index;
+        position.file = start.file;
+    });
+    Some(new_node)
+}
+
+/// Shift the positions of `node` at or after `old_end`, skipping the children before it.
+fn shift_after(node: &mut Jsona, old_end: &Position, new_end: &Position) {
+    let (position, end) = node.positions_mut();
+    shift(position, old_end, new_end);
+    shift(end, old_end, new_end);
+    for anno in node.get_annotations_mut() {
+        shift(&mut anno.position, old_end, new_end);
+        shift(&mut anno.end, old_end, new_end);
+        shift_after(&mut anno.value, old_end, new_end);
+    }
+    // Children are in text order, all before the last one starting before `old_end` stay.
+    match node {
+        Jsona::Array(Array { elements, .. }) => {
+            let first = elements.partition_point(|v| v.get_position().index < old_end.index);
+            for elem in &mut elements[first.saturating_sub(1)..] {
+                shift_after(elem, old_end, new_end);
+            }
+        }
+        Jsona::Object(Object { properties, .. }) => {
+            let first = properties.partition_point(|p| p.position.index < old_end.index);
+            for prop in &mut properties[first.saturating_sub(1)..] {
+                shift(&mut prop.position, old_end, new_end);
+                shift_after(&mut prop.value, old_end, new_end);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Move a position at or after the edit from `old_end` to `new_end`.
+fn shift(position: &mut Position, old_end: &Position, new_end: &Position) {
+    if position.index < old_end.index {
+        return;
+    }
+    if position.line == old_end.line {
+        position.col = position.col - old_end.col + new_end.col;
+    }
+    position.line = position.line - old_end.line + new_end.line;
+    position.index = position.index - old_end.index + new_end.index;
+}
+
+/// The byte offsets and positions of the chars at `indices` of `source`, which must be in
+/// ascending order. Indices past the end resolve to the end of `source`.
+fn locate(source: &str, indices: &[usize]) -> Vec<(usize, Position)> {
+    let mut position = Position::new(0, 1, 1);
+    let mut chars = source.chars();
+    let mut result = Vec::with_capacity(indices.len());
+    for &index in indices {
+        while position.index < index {
+            match chars.next() {
+                Some(ch) => advance(&mut position, ch),
+                None => break,
+            }
+        }
+        result.push((source.len() - chars.as_str().len(), position));
+    }
+    result
+}
+
+/// Move `position` past `ch`.
+fn advance(position: &mut Position, ch: char) {
+    position.index += 1;
+    if ch == '\n' {
+        position.line += 1;
+        position.col = 1;
+    } else {
+        position.col += 1;
+    }
+}
+
+/// Indices of the children leading to the innermost container whose brackets enclose `[start, end)`.
+fn find_container(node: &Jsona, start: usize, end: usize) -> Option<Vec<usize>> {
+    if !(node.is_array() || node.is_object())
+        || start <= node.get_position().index
+        || end >= node.get_end_position().index
+    {
+        return None;
+    }
+    for (i, child) in children(node).enumerate() {
+        if let Some(mut path) = find_container(child, start, end) {
+            path.insert(0, i);
+            return Some(path);
+        }
+    }
+    Some(vec![])
+}
+
+fn children(node: &Jsona) -> Box<dyn Iterator<Item = &Jsona> + '_> {
+    match node {
+        Jsona::Array(Array { elements, .. }) => Box::new(elements.iter()),
+        Jsona::Object(Object { properties, .. }) => Box::new(properties.iter().map(|p| &p.value)),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn node_at<'a>(doc: &'a Jsona, path: &[usize]) -> &'a Jsona {
+    path.iter()
+        .fold(doc, |node, i| children(node).nth(*i).unwrap())
+}
+
+fn node_at_mut<'a>(doc: &'a mut Jsona, path: &[usize]) -> &'a mut Jsona {
+    path.iter().fold(doc, |node, i| match node {
+        Jsona::Array(Array { elements, .. }) => &mut elements[*i],
+        Jsona::Object(Object { properties, .. }) => &mut properties[*i].value,
+        _ => unreachable!(),
+    })
+}
@@ -0,0 +1,482 @@
+//! Conversion between annotated JSONA documents and JSON Schema (draft 2020-12).
+//!
+//! For export the document is read as in [`schema`](crate::schema): types come from the nodes or
+//! `@type`, properties are required unless `@optional`, and `@min`/`@max`, `@pattern`,
+//! `@enum` and `@format` map to their keywords. `@description` becomes `description`, and
+//! nodes named with `@save("name")` go to `$defs`, referred to by `$ref` there and at their
+//! `@use("name")` sites. Import is the reverse, giving a document of representative values.
+
+use std::collections::HashSet;
+
+use serde_json::{Map, Value};
+
+use crate::error::{Error, ErrorKind};
+use crate::pointer;
+use crate::resolve::{SAVE, USE};
+use crate::schema::{is_type, schema_type, ENUM, FORMAT, MAX, MIN, OPTIONAL, PATTERN, TYPE};
+use crate::syntax::{Annotation, Array, Jsona, Object, Position, Property};
+
+pub const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+pub const DESCRIPTION: &str = "description";
+
+/// Convert `doc` to a JSON Schema.
+pub fn to_json_schema(doc: &Jsona) -> Value {
+    let mut defs = Map::new();
+    let root = node_schema(doc, &mut defs);
+    let mut output = Map::new();
+    output.insert("$schema".into(), DRAFT.into());
+    output.extend(root);
+    if !defs.is_empty() {
+        output.insert("$defs".into(), Value::Object(defs));
+    }
+    Value::Object(output)
+}
+
+fn node_schema(node: &Jsona, defs: &mut Map<String, Value>) -> Map<String, Value> {
+    let mut output = Map::new();
+    if let Ok(Some(name)) = node.annotation_as::<String>(USE) {
+        output.insert("$ref".into(), reference(&name));
+        keywords(node, None, &mut output);
+        return output;
+    }
+    let types = types(node);
+    match types.as_slice() {
+        [] => {}
+        [name] => {
+            output.insert("type".into(), name.as_str().into());
+        }
+        _ => {
+            output.insert("type".into(), types.clone().into());
+        }
+    }
+    match node {
+        Jsona::Object(Object { properties, .. }) => {
+            let mut schemas = Map::new();
+            let mut required = Vec::new();
+            for prop in properties {
+                schemas.insert(
+                    prop.key.clone(),
+                    Value::Object(node_schema(&prop.value, defs)),
+                );
+                if !prop.value.has_annotation(OPTIONAL) {
+                    required.push(Value::from(prop.key.as_str()));
+                }
+            }
+            if !schemas.is_empty() {
+                output.insert("properties".into(), Value::Object(schemas));
+            }
+            if !required.is_empty() {
+                output.insert("required".into(), Value::Array(required));
+            }
+        }
+        Jsona::Array(Array { elements, .. }) => match elements.as_slice() {
+            [] => {}
+            [elem] => {
+                output.insert("items".into(), Value::Object(node_schema(elem, defs)));
+            }
+            _ => {
+                let schemas = elements
+                    .iter()
+                    .map(|v| Value::Object(node_schema(v, defs)))
+                    .collect();
+                let mut items = Map::new();
+                items.insert("anyOf".into(), Value::Array(schemas));
+                output.insert("items".into(), Value::Object(items));
+            }
+        },
+        _ => {}
+    }
+    let kind = match types.as_slice() {
+        [name] => Some(name.as_str()),
+        _ => None,
+    };
+    keywords(node, kind, &mut output);
+    match node.annotation_as::<String>(SAVE) {
+        Ok(Some(name)) => {
+            let mut site = Map::new();
+            site.insert("$ref".into(), reference(&name));
+            defs.insert(name, Value::Object(output));
+            site
+        }
+        _ => output,
+    }
+}
+
+/// The JSON Schema type names of `node`, empty for any type.
+fn types(node: &Jsona) -> Vec<String> {
+    let types = match node.annotation_as::<Value>(TYPE) {
+        Ok(Some(Value::String(name))) => vec![name],
+        Ok(Some(Value::Array(names))) => names
+            .iter()
+            .filter_map(|v| v.as_str().map(|v| v.to_string()))
+            .collect(),
+        _ => vec![schema_type(node).to_string()],
+    };
+    if types.iter().any(|v| v == "any") {
+        Vec::new()
+    } else {
+        types
+    }
+}
+
+/// Add the keywords of annotations, bounds apply to values of `kind`.
+fn keywords(node: &Jsona, kind: Option<&str>, output: &mut Map<String, Value>) {
+    if let Ok(Some(description)) = node.annotation_as::<String>(DESCRIPTION) {
+        output.insert("description".into(), description.into());
+    }
+    if let Ok(Some(values)) = node.annotation_as::<Vec<Value>>(ENUM) {
+        output.insert("enum".into(), values.into());
+    }
+    if let Ok(Some(pattern)) = node.annotation_as::<String>(PATTERN) {
+        output.insert("pattern".into(), pattern.into());
+    }
+    if let Ok(Some(format)) = node.annotation_as::<String>(FORMAT) {
+        output.insert("format".into(), format.into());
+    }
+    let (min, max) = match kind {
+        Some("integer") | Some("number") => ("minimum", "maximum"),
+        Some("string") => ("minLength", "maxLength"),
+        Some("array") => ("minItems", "maxItems"),
+        Some("object") => ("minProperties", "maxProperties"),
+        _ => return,
+    };
+    for (name, keyword) in [(MIN, min), (MAX, max)] {
+        match node.annotation_as::<Value>(name) {
+            // Lengths are non-negative integers.
+            Ok(Some(Value::Number(bound))) if min == "minimum" || bound.is_u64() => {
+                output.insert(keyword.into(), Value::Number(bound));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn reference(name: &str) -> Value {
+    format!("#{}", pointer::join(&["$defs", name])).into()
+}
+
+/// Generate an example document from a JSON Schema, with its constraints as annotations.
+///
+/// Values are taken from `const`, `default`, `examples`, `enum` or `minimum`, else they are
+/// empty values of their type, or samples for known formats. `allOf` is merged, of `anyOf` and
+/// `oneOf` the first schema is used. The first `$ref` to a definition in `$defs` or
+/// `definitions` inlines it with `@save("name")`, later ones become `@use("name")`. Other
+/// references are an error. `exclusiveMinimum` and `exclusiveMaximum` become `@min` and `@max`
+/// for integers, for other numbers they are lost as the bounds of JSONA are inclusive.
+pub fn from_json_schema(schema: &Value) -> Result<Jsona, Error> {
+    let mut importer = Importer {
+        root: schema,
+        saved: HashSet::new(),
+    };
+    importer.import(schema)
+}
+
+struct Importer<'a> {
+    root: &'a Value,
+    saved: HashSet<String>,
+}
+
+impl<'a> Importer<'a> {
+    fn import(&mut self, schema: &Value) -> Result<Jsona, Error> {
+        let schema = match schema {
+            Value::Object(map) => map,
+            _ => return Ok(Jsona::from(Value::Null).with_annotation(type_annotation("any"))),
+        };
+        if let Some(Value::String(reference)) = schema.get("$ref") {
+            return self.import_reference(reference, schema);
+        }
+        let schema = merge_all_of(schema);
+        for keyword in ["anyOf", "oneOf"] {
+            if let Some(Value::Array(schemas)) = schema.get(keyword) {
+                if let Some(first) = schemas.first() {
+                    let mut node = self.import(first)?;
+                    annotate(&mut node, &schema, None);
+                    return Ok(node);
+                }
+            }
+        }
+        let mut types: Vec<&str> = match schema.get("type") {
+            Some(Value::String(name)) => vec![name],
+            Some(Value::Array(names)) => names.iter().filter_map(|v| v.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if types.is_empty() {
+            if let Some(name) = infer_type(&schema) {
+                types.push(name);
+            }
+        }
+        let kind = types
+            .iter()
+            .copied()
+            .find(|v| *v != "null")
+            .or_else(|| types.first().copied());
+        let mut node = match kind {
+            Some("object") => {
+                let mut node = Jsona::from(Value::Object(Map::new()));
+                let required: Vec<&str> = match schema.get("required") {
+                    Some(Value::Array(keys)) => keys.iter().filter_map(|v| v.as_str()).collect(),
+                    _ => Vec::new(),
+                };
+                if let (Some(Value::Object(properties)), Jsona::Object(object)) =
+                    (schema.get("properties"), &mut node)
+                {
+                    for (key, value) in properties {
+                        let mut value = self.import(value)?;
+                        if !required.contains(&key.as_str()) {
+                            value
+                                .get_annotations_mut()
+                                .push(Annotation::new(OPTIONAL, Value::Null));
+                        }
+                        object.properties.push(Property::new(key, value));
+                    }
+                }
+                node
+            }
+            Some("array") => {
+                let mut elements = Vec::new();
+                match (schema.get("prefixItems"), schema.get("items")) {
+                    (Some(Value::Array(schemas)), _) => {
+                        for schema in schemas {
+                            elements.push(self.import(schema)?);
+                        }
+                    }
+                    (_, Some(Value::Object(items))) => match items.get("anyOf") {
+                        Some(Value::Array(schemas)) if items.len() == 1 => {
+                            for schema in schemas {
+                                elements.push(self.import(schema)?);
+                            }
+                        }
+                        _ => elements.push(self.import(&Value::Object(items.clone()))?),
+                    },
+                    _ => {}
+                }
+                let mut node = Jsona::from(Value::Array(Vec::new()));
+                if let Jsona::Array(array) = &mut node {
+                    array.elements = elements;
+                }
+                node
+            }
+            Some(kind) => Jsona::from(sample(&schema, kind)),
+            None => Jsona::from(Value::Null),
+        };
+        match types.as_slice() {
+            [] => node.get_annotations_mut().push(type_annotation("any")),
+            [_] => {}
+            _ => {
+                let names = types.iter().map(|v| Value::from(*v)).collect();
+                node.get_annotations_mut()
+                    .push(Annotation::new(TYPE, Value::Array(names)));
+            }
+        }
+        annotate(&mut node, &schema, kind);
+        Ok(node)
+    }
+    fn import_reference(
+        &mut self,
+        reference: &str,
+        schema: &Map<String, Value>,
+    ) -> Result<Jsona, Error> {
+        let unsupported = || {
+            Error::new(
+                format!("unsupported reference '{}'", reference),
+                Position::default(),
+            )
+            .with_kind(ErrorKind::Reference)
+        };
+        let fragment = reference
+            .strip_prefix('#')
+            .and_then(percent_decode)
+            .ok_or_else(unsupported)?;
+        let tokens = pointer::split(&fragment).ok_or_else(unsupported)?;
+        let name = match tokens.as_slice() {
+            [defs, name] if defs == "$defs" || defs == "definitions" => name.clone(),
+            _ => return Err(unsupported()),
+        };
+        let definition = self.root.pointer(&fragment).ok_or_else(unsupported)?;
+        let mut node = if self.saved.insert(name.clone()) {
+            let mut node = self.import(definition)?;
+            node.get_annotations_mut()
+                .insert(0, Annotation::new(SAVE, Value::from(name)));
+            node
+        } else {
+            Jsona::from(Value::Null).with_annotation(Annotation::new(USE, Value::from(name)))
+        };
+        annotate(&mut node, schema, None);
+        Ok(node)
+    }
+}
+
+/// Decode the `%XX` escapes of a URI fragment, `None` if they are malformed.
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut iter = text.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// The schema with the schemas of `allOf` merged into it.
+fn merge_all_of(schema: &Map<String, Value>) -> Map<String, Value> {
+    let mut output = schema.clone();
+    let schemas = match output.remove("allOf") {
+        Some(Value::Array(schemas)) => schemas,
+        _ => return output,
+    };
+    for schema in schemas {
+        let schema = match schema {
+            Value::Object(map) => merge_all_of(&map),
+            _ => continue,
+        };
+        for (key, value) in schema {
+            match (output.get_mut(&key), value) {
+                (Some(Value::Object(a)), Value::Object(b)) if key == "properties" => a.extend(b),
+                (Some(Value::Array(a)), Value::Array(b)) if key == "required" => a.extend(b),
+                (_, value) => {
+                    output.insert(key, value);
+                }
+            }
+        }
+    }
+    output
+}
+
+fn infer_type(schema: &Map<String, Value>) -> Option<&'static str> {
+    if schema.contains_key("properties") {
+        return Some("object");
+    }
+    if schema.contains_key("items") || schema.contains_key("prefixItems") {
+        return Some("array");
+    }
+    let value = match (schema.get("const"), schema.get("enum")) {
+        (Some(value), _) => value,
+        (_, Some(Value::Array(values))) => values.first()?,
+        _ => return None,
+    };
+    Some(match value {
+        Value::Null => "null",
+        Value::Bool(..) => "boolean",
+        Value::Number(v) if v.is_f64() => "number",
+        Value::Number(..) => "integer",
+        Value::String(..) => "string",
+        Value::Array(..) => "array",
+        Value::Object(..) => "object",
+    })
+}
+
+/// A representative scalar of type `kind`.
+fn sample(schema: &Map<String, Value>, kind: &str) -> Value {
+    let minimum = numeric_bound(schema, kind, false);
+    let candidates = [
+        schema.get("const"),
+        schema.get("default"),
+        schema.get("examples").and_then(|v| v.get(0)),
+        schema.get("enum").and_then(|v| v.get(0)),
+        minimum.as_ref(),
+    ];
+    let value = candidates
+        .iter()
+        .flatten()
+        .find(|v| is_type(kind, v))
+        .map(|v| (*v).clone());
+    let value = value.unwrap_or_else(|| match kind {
+        "boolean" => false.into(),
+        "integer" => 0.into(),
+        "number" => 0.0.into(),
+        "string" => match schema.get("format").and_then(|v| v.as_str()) {
+            Some("date") => "1970-01-01".into(),
+            Some("time") => "00:00:00Z".into(),
+            Some("date-time") => "1970-01-01T00:00:00Z".into(),
+            Some("email") => "user@example.com".into(),
+            Some("hostname") => "example.com".into(),
+            Some("ipv4") => "127.0.0.1".into(),
+            Some("ipv6") => "::1".into(),
+            Some("uri") => "https://example.com".into(),
+            Some("uuid") => "00000000-0000-0000-0000-000000000000".into(),
+            _ => "".into(),
+        },
+        _ => Value::Null,
+    });
+    // Keep numbers floats, so the type reads back as `number`.
+    match value.as_f64() {
+        Some(v) if kind == "number" && !value.is_f64() => v.into(),
+        _ => value,
+    }
+}
+
+/// Add annotations for the keywords of `schema`, bounds are read for values of `kind`.
+fn annotate(node: &mut Jsona, schema: &Map<String, Value>, kind: Option<&str>) {
+    let mut annotations = Vec::new();
+    for (keyword, name) in [
+        ("description", DESCRIPTION),
+        ("enum", ENUM),
+        ("pattern", PATTERN),
+        ("format", FORMAT),
+    ] {
+        if let Some(value) = schema.get(keyword) {
+            annotations.push(Annotation::new(name, value));
+        }
+    }
+    if let Some(kind @ "integer") | Some(kind @ "number") = kind {
+        for (upper, name) in [(false, MIN), (true, MAX)] {
+            if let Some(value) = numeric_bound(schema, kind, upper) {
+                annotations.push(Annotation::new(name, value));
+            }
+        }
+    }
+    let bounds = match kind {
+        Some("string") => Some(["minLength", "maxLength"]),
+        Some("array") => Some(["minItems", "maxItems"]),
+        Some("object") => Some(["minProperties", "maxProperties"]),
+        _ => None,
+    };
+    for (keyword, name) in bounds.iter().flatten().zip([MIN, MAX]) {
+        if let Some(value @ Value::Number(..)) = schema.get(*keyword) {
+            annotations.push(Annotation::new(name, value));
+        }
+    }
+    node.get_annotations_mut().extend(annotations);
+}
+
+/// The inclusive lower or upper bound for numbers of `kind`, exclusive bounds are only
+/// converted for integers.
+fn numeric_bound(schema: &Map<String, Value>, kind: &str, upper: bool) -> Option<Value> {
+    let (inclusive, exclusive) = if upper {
+        ("maximum", "exclusiveMaximum")
+    } else {
+        ("minimum", "exclusiveMinimum")
+    };
+    let inclusive = schema.get(inclusive).filter(|v| v.is_number()).cloned();
+    // Exclusive integer bounds outside of `i64` are dropped.
+    let limit = 2f64.powi(63);
+    let exclusive = match schema.get(exclusive).and_then(Value::as_f64) {
+        Some(v) if kind == "integer" && upper && v.ceil() >= -limit && v.ceil() < limit => {
+            (v.ceil() as i64).checked_sub(1).map(Value::from)
+        }
+        Some(v) if kind == "integer" && v.floor() >= -limit && v.floor() < limit => {
+            (v.floor() as i64).checked_add(1).map(Value::from)
+        }
+        _ => None,
+    };
+    match (inclusive, exclusive) {
+        (Some(a), Some(b)) => {
+            let tighter = if upper {
+                b.as_f64() < a.as_f64()
+            } else {
+                b.as_f64() > a.as_f64()
+            };
+            Some(if tighter { b } else { a })
+        }
+        (a, b) => a.or(b),
+    }
+}
+
+fn type_annotation(name: &str) -> Annotation {
+    Annotation::new(TYPE, Value::from(name))
+}
@@ -1,6 +1,7 @@
 use std::fmt::{self, Display, Formatter};
 use std::str::FromStr;
 
+use crate::error::ErrorKind;
 use crate::syntax::Position;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -15,13 +16,13 @@ impl Token {
         Self { kind, position }
     }
     pub fn is_value(&self) -> bool {
-        match self.kind {
+        matches!(
+            self.kind,
             TokenKind::Identifier(..)
-            | TokenKind::IntegerLiteral(..)
-            | TokenKind::FloatLiteral(..)
-            | TokenKind::StringLiteral(..) => true,
-            _ => false,
-        }
+                | TokenKind::IntegerLiteral(..)
+                | TokenKind::FloatLiteral(..)
+                | TokenKind::StringLiteral(..)
+        )
     }
     pub fn get_value(&self) -> Option<String> {
         if self.is_value() {
@@ -37,15 +38,15 @@ impl Token {
         }
     }
     pub fn is_node(&self) -> bool {
-        match self.kind {
+        matches!(
+            self.kind,
             TokenKind::LeftBrace
-            | TokenKind::LeftBracket
-            | TokenKind::Identifier(..)
-            | TokenKind::IntegerLiteral(..)
-            | TokenKind::FloatLiteral(..)
-            | TokenKind::StringLiteral(..) => true,
-            _ => false,
-        }
+                | TokenKind::LeftBracket
+                | TokenKind::Identifier(..)
+                | TokenKind::IntegerLiteral(..)
+                | TokenKind::FloatLiteral(..)
+                | TokenKind::StringLiteral(..)
+        )
     }
 }
 
@@ -85,8 +86,10 @@ pub enum TokenKind {
     FloatLiteral(f64),
     /// A string literal
     StringLiteral(String),
+    /// A `//` or `/* */` comment, only produced if enabled with [`Lexer::with_comments`].
+    Comment(String),
     /// A lexer error.
-    LexError(String),
+    LexError(ErrorKind, String),
     /// Eof
     Eof,
 }
@@ -108,7 +111,8 @@ impl Display for TokenKind {
             TokenKind::IntegerLiteral(i) => write!(f, "{}", i),
             TokenKind::FloatLiteral(v) => write!(f, "{}", v),
             TokenKind::StringLiteral(s) => write!(f, "{}", s),
-            TokenKind::LexError(err) => write!(f, "{}", err),
+            TokenKind::Comment(s) => write!(f, "{}", s),
+            TokenKind::LexError(_, err) => write!(f, "{}", err),
             TokenKind::Eof => write!(f, "eof"),
         }
     }
@@ -116,28 +120,17 @@ impl Display for TokenKind {
 
 /// Test if the given character is a hex character.
 fn is_hex_char(c: char) -> bool {
-    match c {
-        'a'..='f' => true,
-        'A'..='F' => true,
-        '0'..='9' => true,
-        _ => false,
-    }
+    c.is_ascii_hexdigit()
 }
 
 /// Test if the given character is an octal character.
 fn is_octal_char(c: char) -> bool {
-    match c {
-        '0'..='7' => true,
-        _ => false,
-    }
+    matches!(c, '0'..='7')
 }
 
 /// Test if the given character is a binary character.
 fn is_binary_char(c: char) -> bool {
-    match c {
-        '0' | '1' => true,
-        _ => false,
-    }
+    matches!(c, '0' | '1')
 }
 
 pub struct Lexer<T> {
@@ -145,6 +138,7 @@ pub struct Lexer<T> {
     buf: Option<char>,
     pos: Position,
     eof: bool,
+    comments: bool,
 }
 
 impl<T: Iterator<Item = char>> Lexer<T> {
@@ -154,8 +148,18 @@ impl<T: Iterator<Item = char>> Lexer<T> {
             buf: None,
             pos: Position::new(0, 1, 1),
             eof: false,
+            comments: false,
         }
     }
+    /// Produce `Comment` tokens instead of skipping comments.
+    pub fn with_comments(mut self, comments: bool) -> Self {
+        self.comments = comments;
+        self
+    }
+    /// The position right after the last scanned token.
+    pub fn position(&self) -> Position {
+        self.pos
+    }
     fn step(&mut self, ch: char) {
         if ch == '\n' {
             self.pos.index += 1;
@@ -171,10 +175,8 @@ impl<T: Iterator<Item = char>> Lexer<T> {
             if let Some(ch) = self.buf {
                 self.buf = None;
                 Some(ch)
-            } else if let Some(ch) = self.input.next() {
-                Some(ch)
             } else {
-                None
+                self.input.next()
             }
         };
         if let Some(c) = ch {
@@ -209,7 +211,7 @@ impl<T: Iterator<Item = char>> Lexer<T> {
         self.peek_ch().map(|c| c == ch).unwrap_or(false)
     }
     fn scan_next_token(&mut self) -> Option<Token> {
-        let mut start_pos = self.pos.clone();
+        let mut start_pos = self.pos;
         while let Some(ch) = self.next_ch() {
             match (ch, self.peek_ch().unwrap_or('\0')) {
                 ('@', _) => return Some(Token::new(TokenKind::At, start_pos)),
@@ -236,29 +238,45 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                 }
                 ('/', '*') => {
                     self.next_ch();
+                    let mut text = String::from("/*");
                     loop {
                         if let Some(ch) = self.next_ch() {
+                            text.push(ch);
                             if ch == '*' && self.peek_ch_is('/') {
-                                self.next();
+                                self.next_ch();
+                                text.push('/');
                                 break;
                             }
                         } else {
                             return Some(Token::new(
-                                TokenKind::LexError("unterminated multiline comment".into()),
-                                self.pos.clone(),
+                                TokenKind::LexError(
+                                    ErrorKind::UnterminatedComment,
+                                    "unterminated multiline comment".into(),
+                                ),
+                                self.pos,
                             ));
                         }
                     }
+                    if self.comments {
+                        return Some(Token::new(TokenKind::Comment(text), start_pos));
+                    }
+                    start_pos = self.pos;
                 }
                 ('/', '/') => {
                     self.next_ch();
+                    let mut text = String::from("//");
                     while let Some(ch) = self.peek_ch() {
                         if ch == '\n' {
                             break;
                         } else {
+                            text.push(ch);
                             self.next_ch();
                         }
                     }
+                    if self.comments {
+                        return Some(Token::new(TokenKind::Comment(text), start_pos));
+                    }
+                    start_pos = self.pos;
                 }
                 ('"', _) => return self.scan_string_literal(start_pos, '"'),
                 ('`', _) => return self.scan_string_literal(start_pos, '`'),
@@ -268,12 +286,15 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                 }
                 ('0'..='9', _) => return self.scan_number_literal(start_pos, ch, false),
                 (ch, _) if ch.is_whitespace() || ch == '\n' => {
-                    start_pos = self.pos.clone();
+                    start_pos = self.pos;
                 }
                 (ch, _) => {
                     return Some(Token::new(
-                        TokenKind::LexError(format!("unexpected input '{}'", ch)),
-                        self.pos.clone(),
+                        TokenKind::LexError(
+                            ErrorKind::UnexpectedCharacter,
+                            format!("unexpected input '{}'", ch),
+                        ),
+                        self.pos,
                     ))
                 }
             }
@@ -289,7 +310,13 @@ impl<T: Iterator<Item = char>> Lexer<T> {
         loop {
             let ch = self.next_ch();
             if ch.is_none() {
-                return Some(Token::new(TokenKind::Eof, self.pos.clone()));
+                return Some(Token::new(
+                    TokenKind::LexError(
+                        ErrorKind::UnterminatedString,
+                        "unterminated string literal".into(),
+                    ),
+                    start_pos,
+                ));
             }
             let ch = ch.unwrap();
             if ch == enclosing_char {
@@ -301,19 +328,30 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                     continue;
                 } else {
                     return Some(Token::new(
-                        TokenKind::LexError("Unexpected line break".into()),
-                        self.pos.clone(),
+                        TokenKind::LexError(
+                            ErrorKind::UnterminatedString,
+                            "Unexpected line break".into(),
+                        ),
+                        self.pos,
                     ));
                 }
             }
             if ch != '\\' {
-                buf.push(ch as u16);
+                buf.extend(ch.encode_utf16(&mut [0u16; 2]).iter());
                 continue;
             }
 
             let next_ch = match self.next_ch() {
                 Some(ch) => ch,
-                None => return Some(Token::new(TokenKind::Eof, self.pos.clone())),
+                None => {
+                    return Some(Token::new(
+                        TokenKind::LexError(
+                            ErrorKind::UnterminatedString,
+                            "unterminated string literal".into(),
+                        ),
+                        start_pos,
+                    ))
+                }
             };
 
             match next_ch {
@@ -330,8 +368,11 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                     let chars = self.next_chars_util(|c, i| i > 1 || !is_hex_char(c));
                     if chars.len() != 2 {
                         return Some(Token::new(
-                            TokenKind::LexError("invalid hexadecimal escape sequence".into()),
-                            self.pos.clone(),
+                            TokenKind::LexError(
+                                ErrorKind::InvalidEscape,
+                                "invalid hexadecimal escape sequence".into(),
+                            ),
+                            self.pos,
                         ));
                     }
                     buf.push(
@@ -346,16 +387,17 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                             let chars = self.next_chars_util(|c, _| c == '}');
                             if let Some('}') = self.next_ch() {
                                 let code_point = match u32::from_str_radix(
-                                    &chars.iter().collect::<String>().as_str(),
+                                    chars.iter().collect::<String>().as_str(),
                                     16,
                                 ) {
                                     Err(_) => {
                                         return Some(Token::new(
                                             TokenKind::LexError(
+                                                ErrorKind::InvalidEscape,
                                                 "malformed Unicode character escape sequence"
                                                     .into(),
                                             ),
-                                            self.pos.clone(),
+                                            self.pos,
                                         ));
                                     }
                                     Ok(v) => v,
@@ -363,7 +405,7 @@ impl<T: Iterator<Item = char>> Lexer<T> {
 
                                 // UTF16Encoding of a numeric code point value
                                 if code_point > 0x10_FFFF {
-                                    return Some(Token::new(TokenKind::LexError("Unicode codepoint must not be greater than 0x10FFFF in escape sequence".into()), self.pos.clone()));
+                                    return Some(Token::new(TokenKind::LexError(ErrorKind::InvalidEscape, "Unicode codepoint must not be greater than 0x10FFFF in escape sequence".into()), self.pos));
                                 } else if code_point <= 65535 {
                                     buf.push(code_point as u16);
                                 } else {
@@ -374,8 +416,11 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                                 }
                             } else {
                                 return Some(Token::new(
-                                    TokenKind::LexError("invalid Unicode escape sequence".into()),
-                                    self.pos.clone(),
+                                    TokenKind::LexError(
+                                        ErrorKind::InvalidEscape,
+                                        "invalid Unicode escape sequence".into(),
+                                    ),
+                                    self.pos,
                                 ));
                             }
                         }
@@ -384,15 +429,16 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                             let chars = self.next_chars_util(|_, i| i > 3);
                             // Convert to u16
                             let code_point = match u16::from_str_radix(
-                                &chars.iter().collect::<String>().as_str(),
+                                chars.iter().collect::<String>().as_str(),
                                 16,
                             ) {
                                 Err(_) => {
                                     return Some(Token::new(
                                         TokenKind::LexError(
+                                            ErrorKind::InvalidEscape,
                                             "malformed Unicode character escape sequence".into(),
                                         ),
-                                        self.pos.clone(),
+                                        self.pos,
                                     ));
                                 }
                                 Ok(v) => v,
@@ -401,8 +447,11 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                         }
                         None => {
                             return Some(Token::new(
-                                TokenKind::LexError("Unexpected line break".into()),
-                                self.pos.clone(),
+                                TokenKind::LexError(
+                                    ErrorKind::UnterminatedString,
+                                    "Unexpected line break".into(),
+                                ),
+                                self.pos,
                             ))
                         }
                     }
@@ -413,7 +462,7 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                         let mut chars = self.next_chars_util(|c, i| i > 1 || !is_octal_char(c));
                         chars.insert(0, next_ch);
                         buf.push(
-                            u16::from_str_radix(&chars.iter().collect::<String>().as_str(), 8)
+                            u16::from_str_radix(chars.iter().collect::<String>().as_str(), 8)
                                 .unwrap(),
                         );
                     } else {
@@ -511,7 +560,10 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                     i
                 } else {
                     return Some(Token::new(
-                        TokenKind::LexError(format!("unexpected number literal {}", out)),
+                        TokenKind::LexError(
+                            ErrorKind::InvalidNumber,
+                            format!("unexpected number literal {}", out),
+                        ),
                         start_pos,
                     ));
                 }
@@ -525,7 +577,10 @@ impl<T: Iterator<Item = char>> Lexer<T> {
                 Some(Token::new(TokenKind::FloatLiteral(f), start_pos))
             } else {
                 Some(Token::new(
-                    TokenKind::LexError(format!("unexpected number literal {}", out)),
+                    TokenKind::LexError(
+                        ErrorKind::InvalidNumber,
+                        format!("unexpected number literal {}", out),
+                    ),
                     start_pos,
                 ))
             }
@@ -536,7 +591,7 @@ impl<T: Iterator<Item = char>> Lexer<T> {
         result.push(first_char);
 
         while let Some(next_char) = self.peek_ch() {
-            if next_char.is_alphabetic() || next_char.is_digit(10) || next_char == '_' {
+            if next_char.is_alphabetic() || next_char.is_ascii_digit() || next_char == '_' {
                 result.push(next_char);
                 self.next_ch();
             } else {
@@ -546,7 +601,7 @@ impl<T: Iterator<Item = char>> Lexer<T> {
 
         let identifier = result.into_iter().collect();
 
-        return Some(Token::new(TokenKind::Identifier(identifier), start_pos));
+        Some(Token::new(TokenKind::Identifier(identifier), start_pos))
     }
 }
 
@@ -570,11 +625,7 @@ mod tests {
             token,
             Some(Token::new(
                 TokenKind::StringLiteral("\u{0}\u{8}\u{c}\n\r\t\u{b}\'\\©©你".into()),
-                Position {
-                    index: 0,
-                    line: 1,
-                    col: 1
-                }
+                Position::new(0, 1, 1)
             ))
         );
     }
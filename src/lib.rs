@@ -1,12 +1,74 @@
+#[macro_use]
+mod macros;
+mod de;
+
 pub mod syntax;
 pub mod error;
+pub mod diagnostic;
+pub mod diff;
+pub mod emitter;
+pub mod highlight;
+pub mod include;
+pub mod incremental;
+pub mod json_schema;
 pub mod lexer;
 pub mod loader;
+pub mod lsp;
+pub mod matcher;
+pub mod mock;
 pub mod parser;
+pub mod patch;
+pub mod pointer;
+pub mod registry;
+pub mod resolve;
+pub mod schema;
+pub mod semantic;
+pub mod source;
+pub mod typescript;
 
 pub use syntax::Jsona;
 pub use error::Error;
 
+use serde::Serialize;
+
+#[doc(hidden)]
+pub mod __private {
+    pub use serde_json::Value;
+    pub use std::string::ToString;
+    pub use std::vec::Vec;
+}
+
+/// Parse a document. Nesting is not limited, see [`from_str_with_max_depth`] for untrusted
+/// input.
 pub fn from_str(input: &str) -> Result<Jsona, Error> {
     loader::Loader::load_from_str(input)
 }
+
+/// Parse a document, rejecting arrays and objects nested deeper than `max_depth`, for
+/// example [`parser::MAX_DEPTH`].
+pub fn from_str_with_max_depth(input: &str, max_depth: usize) -> Result<Jsona, Error> {
+    loader::Loader::load_with_max_depth(input, max_depth)
+}
+
+/// Convert any serializable value into a `Jsona` tree with default positions and no annotations.
+///
+/// Integers beyond the range of `i64` are an error, as JSONA integers can not hold them.
+pub fn to_jsona<T: Serialize + ?Sized>(value: &T) -> Result<Jsona, Error> {
+    let value = serde_json::to_value(value)
+        .map_err(|e| Error::new(e.to_string(), syntax::Position::default()))?;
+    check_integers(&value)?;
+    Ok(Jsona::from(value))
+}
+
+fn check_integers(value: &serde_json::Value) -> Result<(), Error> {
+    use serde_json::Value;
+    match value {
+        Value::Number(n) if n.is_u64() && n.as_i64().is_none() => Err(Error::new(
+            format!("integer {} is out of range", n),
+            syntax::Position::default(),
+        )),
+        Value::Array(values) => values.iter().try_for_each(check_integers),
+        Value::Object(map) => map.values().try_for_each(check_integers),
+        _ => Ok(()),
+    }
+}
@@ -1,19 +1,26 @@
-use serde_json::{Map, Value};
 use std::string;
 
 use crate::syntax::*;
-use crate::parser::{Event, EventReceiver, ParseResult, Parser};
+use crate::parser::{Event, ParseResult, Parser, SpannedEventReceiver};
+
+type KeyStack = Vec<Option<(Position, string::String)>>;
 
 pub struct Loader {
     value_stack: Vec<Jsona>,
-    key_stack: Vec<Option<(Position, string::String)>>,
+    key_stack: KeyStack,
     annotation_name: Option<(Position, string::String)>,
-    annotation_value_stack: Vec<Value>,
-    annotation_key_stack: Vec<Option<string::String>>,
+    annotation_value_stack: Vec<Jsona>,
+    annotation_key_stack: KeyStack,
 }
 
 impl Loader {
     pub fn load_from_str(input: &str) -> ParseResult<Jsona> {
+        Self::load_with_max_depth(input, usize::MAX)
+    }
+    /// Like [`load_from_str`](Self::load_from_str), but nesting deeper than `max_depth`
+    /// fails with [`ErrorKind::LimitExceeded`](crate::error::ErrorKind::LimitExceeded), see
+    /// [`MAX_DEPTH`](crate::parser::MAX_DEPTH).
+    pub fn load_with_max_depth(input: &str, max_depth: usize) -> ParseResult<Jsona> {
         let mut loader = Loader {
             value_stack: Vec::new(),
             key_stack: Vec::new(),
@@ -21,15 +28,27 @@ impl Loader {
             annotation_value_stack: Vec::new(),
             annotation_key_stack: Vec::new(),
         };
-        let mut parser = Parser::new(input.chars());
+        let mut parser = Parser::new(input.chars()).with_max_depth(max_depth);
         parser.parse(&mut loader)?;
         Ok(loader.value_stack.pop().unwrap())
     }
+    /// The stacks to build nodes on, annotation values are built apart from the document.
+    fn stacks(&mut self) -> (&mut Vec<Jsona>, &mut KeyStack) {
+        if self.annotation_name.is_none() {
+            (&mut self.value_stack, &mut self.key_stack)
+        } else {
+            (
+                &mut self.annotation_value_stack,
+                &mut self.annotation_key_stack,
+            )
+        }
+    }
     fn insert_ast_node(&mut self, node: Jsona) {
-        if self.value_stack.is_empty() {
-            self.value_stack.push(node);
+        let (value_stack, key_stack) = self.stacks();
+        if value_stack.is_empty() {
+            value_stack.push(node);
         } else {
-            let parent = self.value_stack.last_mut().unwrap();
+            let parent = value_stack.last_mut().unwrap();
             match *parent {
                 Jsona::Array(Array {
                     ref mut elements, ..
@@ -37,7 +56,7 @@ impl Loader {
                 Jsona::Object(Object {
                     ref mut properties, ..
                 }) => {
-                    let cur_key = self.key_stack.pop().unwrap();
+                    let cur_key = key_stack.pop().unwrap();
                     let new_key = match cur_key {
                         Some((position, key)) => {
                             properties.push(Property {
@@ -58,39 +77,27 @@ impl Loader {
                             }
                         }
                     };
-                    self.key_stack.push(new_key);
+                    key_stack.push(new_key);
                 }
                 _ => unreachable!(),
             }
         }
     }
-    fn insert_annotation_node(&mut self, node: Value) {
-        if self.annotation_value_stack.is_empty() {
-            self.annotation_value_stack.push(node);
-        } else {
-            let parent = self.annotation_value_stack.last_mut().unwrap();
-            match *parent {
-                Value::Array(ref mut elements) => elements.push(node),
-                Value::Object(ref mut properties) => {
-                    let cur_key = self.annotation_key_stack.pop().unwrap();
-                    let new_key = match cur_key {
-                        Some(key) => {
-                            properties.insert(key, node);
-                            None
-                        }
-                        None => {
-                            if let Value::String(value) = node {
-                                Some(value)
-                            } else {
-                                unreachable!()
-                            }
-                        }
-                    };
-                    self.annotation_key_stack.push(new_key);
-                }
-                _ => unreachable!(),
-            }
+    fn start_container(&mut self, node: Jsona) {
+        let (value_stack, key_stack) = self.stacks();
+        if node.is_object() {
+            key_stack.push(None);
         }
+        value_stack.push(node);
+    }
+    fn stop_container(&mut self, end: Position) {
+        let (value_stack, key_stack) = self.stacks();
+        let mut node = value_stack.pop().unwrap();
+        if node.is_object() {
+            key_stack.pop().unwrap();
+        }
+        node.set_end_position(end);
+        self.insert_ast_node(node);
     }
     fn insert_annotation_value(&mut self, anno: Annotation) {
         let parent = self.value_stack.last_mut().unwrap();
@@ -98,7 +105,7 @@ impl Loader {
             Jsona::Array(Array {
                 ref mut elements, ..
             }) => {
-                if elements.len() > 0 {
+                if !elements.is_empty() {
                     let last_elem = elements.last_mut().unwrap();
                     last_elem.get_annotations_mut().push(anno)
                 } else {
@@ -108,7 +115,7 @@ impl Loader {
             Jsona::Object(Object {
                 ref mut properties, ..
             }) => {
-                if properties.len() > 0 {
+                if !properties.is_empty() {
                     let last_prop = properties.last_mut().unwrap();
                     last_prop.value.get_annotations_mut().push(anno)
                 } else {
@@ -120,123 +127,85 @@ impl Loader {
     }
 }
 
-impl EventReceiver for Loader {
-    fn on_event(&mut self, event: Event, position: Position) {
+impl SpannedEventReceiver for Loader {
+    fn on_event(&mut self, event: Event, position: Position, end: Position) {
         match event {
             Event::AnnotationStart(value) => {
                 self.annotation_name = Some((position, value));
             }
             Event::AnnotationEnd => {
-                let (position, name) = self.annotation_name.take().unwrap();
                 let value = self.annotation_value_stack.pop().unwrap();
+                let (position, name) = self.annotation_name.take().unwrap();
                 self.insert_annotation_value(Annotation {
                     name,
                     position,
+                    end,
                     value,
                 });
             }
             Event::ArrayStart => {
-                if self.annotation_name.is_none() {
-                    self.value_stack.push(Jsona::Array(Array {
-                        elements: Vec::new(),
-                        annotations: Vec::new(),
-                        position,
-                    }));
-                } else {
-                    self.annotation_value_stack.push(Value::Array(Vec::new()));
-                }
-            }
-            Event::ArrayStop => {
-                if self.annotation_name.is_none() {
-                    let node = self.value_stack.pop().unwrap();
-                    self.insert_ast_node(node);
-                } else {
-                    let node = self.annotation_value_stack.pop().unwrap();
-                    self.insert_annotation_node(node);
-                }
+                self.start_container(Jsona::Array(Array {
+                    elements: Vec::new(),
+                    annotations: Vec::new(),
+                    position,
+                    end,
+                }));
             }
             Event::ObjectStart => {
-                if self.annotation_name.is_none() {
-                    self.key_stack.push(None);
-                    self.value_stack.push(Jsona::Object(Object {
-                        properties: Vec::new(),
-                        annotations: Vec::new(),
-                        position,
-                    }));
-                } else {
-                    self.annotation_key_stack.push(None);
-                    self.annotation_value_stack.push(Value::Object(Map::new()));
-                }
+                self.start_container(Jsona::Object(Object {
+                    properties: Vec::new(),
+                    annotations: Vec::new(),
+                    position,
+                    end,
+                    index: KeyIndex::default(),
+                }));
             }
-            Event::ObjectStop => {
-                if self.annotation_name.is_none() {
-                    self.key_stack.pop().unwrap();
-                    let node = self.value_stack.pop().unwrap();
-                    self.insert_ast_node(node);
-                } else {
-                    self.annotation_key_stack.pop().unwrap();
-                    let node = self.annotation_value_stack.pop().unwrap();
-                    self.insert_annotation_node(node);
-                }
+            Event::ArrayStop | Event::ObjectStop => {
+                self.stop_container(end);
             }
             Event::Null => {
-                if self.annotation_name.is_none() {
-                    let node = Jsona::Null(Null {
-                        annotations: Vec::new(),
-                        position,
-                    });
-                    self.insert_ast_node(node);
-                } else {
-                    self.insert_annotation_node(Value::Null);
-                }
+                let node = Jsona::Null(Null {
+                    annotations: Vec::new(),
+                    position,
+                    end,
+                });
+                self.insert_ast_node(node);
             }
             Event::Float(value) => {
-                if self.annotation_name.is_none() {
-                    let node = Jsona::Float(Float {
-                        value,
-                        annotations: Vec::new(),
-                        position,
-                    });
-                    self.insert_ast_node(node);
-                } else {
-                    self.insert_annotation_node(value.into());
-                }
+                let node = Jsona::Float(Float {
+                    value,
+                    annotations: Vec::new(),
+                    position,
+                    end,
+                });
+                self.insert_ast_node(node);
             }
             Event::Integer(value) => {
-                if self.annotation_name.is_none() {
-                    let node = Jsona::Integer(Integer {
-                        value,
-                        annotations: Vec::new(),
-                        position,
-                    });
-                    self.insert_ast_node(node);
-                } else {
-                    self.insert_annotation_node(value.into());
-                }
+                let node = Jsona::Integer(Integer {
+                    value,
+                    annotations: Vec::new(),
+                    position,
+                    end,
+                });
+                self.insert_ast_node(node);
             }
             Event::Boolean(value) => {
-                if self.annotation_name.is_none() {
-                    let node = Jsona::Boolean(Boolean {
-                        value,
-                        annotations: Vec::new(),
-                        position,
-                    });
-                    self.insert_ast_node(node);
-                } else {
-                    self.insert_annotation_node(value.into());
-                }
+                let node = Jsona::Boolean(Boolean {
+                    value,
+                    annotations: Vec::new(),
+                    position,
+                    end,
+                });
+                self.insert_ast_node(node);
             }
             Event::String(value) => {
-                if self.annotation_name.is_none() {
-                    let node = Jsona::String(String {
-                        value,
-                        annotations: Vec::new(),
-                        position,
-                    });
-                    self.insert_ast_node(node);
-                } else {
-                    self.insert_annotation_node(value.into());
-                }
+                let node = Jsona::String(String {
+                    value,
+                    annotations: Vec::new(),
+                    position,
+                    end,
+                });
+                self.insert_ast_node(node);
             }
         }
     }
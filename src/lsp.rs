@@ -0,0 +1,388 @@
+//! A language server speaking JSON-RPC over stdio, started with `jsona lsp`.
+//!
+//! Documents are synced in full. The server publishes diagnostics and provides document
+//! symbols, hover for annotations, formatting, folding ranges and go-to-definition from `@use`
+//! to `@save`.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use serde_json::{json, Value};
+
+use crate::diagnostic::token_width;
+use crate::emitter::{emit_with_comments, format_inline, EmitterOptions};
+use crate::error::Error;
+use crate::resolve::{self, USE};
+use crate::syntax::{Array, Jsona, Located, Object, Position};
+
+/// Serve requests from `input` until the client sends `exit` or closes the stream.
+pub fn run<R: BufRead, W: Write>(mut input: R, output: W) -> io::Result<()> {
+    let mut server = Server {
+        output,
+        documents: HashMap::new(),
+    };
+    while let Some(message) = read_message(&mut input)? {
+        if !server.handle(&message)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn read_message<R: BufRead>(input: &mut R) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(value) = line.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let length = length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length"))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_message<W: Write>(output: &mut W, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+struct Server<W> {
+    output: W,
+    /// Texts of open documents by URI.
+    documents: HashMap<String, String>,
+}
+
+impl<W: Write> Server<W> {
+    /// Handle a message, returns false once the client asked to exit.
+    fn handle(&mut self, message: &Value) -> io::Result<bool> {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let id = match message.get("id") {
+            Some(id) => id,
+            None => {
+                match method {
+                    "exit" => return Ok(false),
+                    "textDocument/didOpen" => {
+                        self.update(params, &params["textDocument"]["text"])?
+                    }
+                    "textDocument/didChange" => {
+                        let changes = params["contentChanges"].as_array();
+                        if let Some(change) = changes.and_then(|v| v.last()) {
+                            self.update(params, &change["text"])?;
+                        }
+                    }
+                    "textDocument/didClose" => {
+                        let uri = uri_of(params);
+                        self.documents.remove(&uri);
+                        self.publish(&uri, Vec::new())?;
+                    }
+                    _ => {}
+                }
+                return Ok(true);
+            }
+        };
+        let result = match method {
+            "initialize" => Some(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "documentSymbolProvider": true,
+                    "hoverProvider": true,
+                    "documentFormattingProvider": true,
+                    "foldingRangeProvider": true,
+                    "definitionProvider": true,
+                },
+                "serverInfo": { "name": "jsona", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "shutdown" => Some(Value::Null),
+            "textDocument/documentSymbol" => Some(self.with_document(params, document_symbols)),
+            "textDocument/hover" => Some(self.with_document(params, hover)),
+            "textDocument/formatting" => Some(self.with_document(params, formatting)),
+            "textDocument/foldingRange" => Some(self.with_document(params, folding_ranges)),
+            "textDocument/definition" => Some(self.with_document(params, definition)),
+            _ => None,
+        };
+        let response = match result {
+            Some(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            None => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": format!("unknown method '{}'", method) },
+            }),
+        };
+        write_message(&mut self.output, &response)?;
+        Ok(true)
+    }
+    fn update(&mut self, params: &Value, text: &Value) -> io::Result<()> {
+        let uri = uri_of(params);
+        let text = text.as_str().unwrap_or_default().to_string();
+        let diagnostics = diagnostics(&uri, &text);
+        self.documents.insert(uri.clone(), text);
+        self.publish(&uri, diagnostics)
+    }
+    fn publish(&mut self, uri: &str, diagnostics: Vec<Value>) -> io::Result<()> {
+        let notification = json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": diagnostics },
+        });
+        write_message(&mut self.output, &notification)
+    }
+    /// Call `f` with the parsed document the request is about, the result is null if the
+    /// document is unknown or does not parse.
+    fn with_document<F>(&self, params: &Value, f: F) -> Value
+    where
+        F: FnOnce(&Document, &Value) -> Value,
+    {
+        let uri = uri_of(params);
+        let text = match self.documents.get(&uri) {
+            Some(text) => text,
+            None => return Value::Null,
+        };
+        match crate::from_str(text) {
+            Ok(root) => f(
+                &Document {
+                    uri: &uri,
+                    text,
+                    root,
+                },
+                params,
+            ),
+            Err(_) => Value::Null,
+        }
+    }
+}
+
+struct Document<'a> {
+    uri: &'a str,
+    text: &'a str,
+    root: Jsona,
+}
+
+impl<'a> Document<'a> {
+    fn line(&self, line: usize) -> &'a str {
+        line_of(self.text, line)
+    }
+    /// LSP positions count lines from 0 and characters in UTF-16 code units.
+    fn lsp_position(&self, position: &Position) -> Value {
+        lsp_position(self.text, position)
+    }
+    fn parse_position(&self, value: &Value) -> Option<Position> {
+        let line = value["line"].as_u64()? as usize + 1;
+        let character = value["character"].as_u64()? as usize;
+        let mut units = 0;
+        let col = self
+            .line(line)
+            .chars()
+            .take_while(|ch| {
+                units += ch.len_utf16();
+                units <= character
+            })
+            .count();
+        Some(Position::new(0, line, col + 1))
+    }
+    fn range(&self, start: &Position, end: &Position) -> Value {
+        json!({ "start": self.lsp_position(start), "end": self.lsp_position(end) })
+    }
+}
+
+fn uri_of(params: &Value) -> String {
+    params["textDocument"]["uri"]
+        .as_str()
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn line_of(text: &str, line: usize) -> &str {
+    text.split('\n')
+        .nth(line.saturating_sub(1))
+        .unwrap_or_default()
+        .trim_end_matches('\r')
+}
+
+fn lsp_position(text: &str, position: &Position) -> Value {
+    let character: usize = line_of(text, position.line)
+        .chars()
+        .take(position.col.saturating_sub(1))
+        .map(char::len_utf16)
+        .sum();
+    json!({ "line": position.line.saturating_sub(1), "character": character })
+}
+
+/// The range of the token at `position`.
+fn token_range(text: &str, position: &Position) -> Value {
+    let mut end = *position;
+    end.col += token_width(line_of(text, position.line), position.col);
+    json!({ "start": lsp_position(text, position), "end": lsp_position(text, &end) })
+}
+
+fn diagnostics(uri: &str, text: &str) -> Vec<Value> {
+    let errors = match crate::from_str(text) {
+        Ok(mut root) => resolve::resolve(&mut root).err().unwrap_or_default(),
+        Err(err) => vec![err],
+    };
+    errors
+        .iter()
+        .map(|err: &Error| {
+            let related: Vec<Value> = err
+                .labels
+                .iter()
+                .map(|label| {
+                    json!({
+                        "location": { "uri": uri, "range": token_range(text, &label.position) },
+                        "message": label.message,
+                    })
+                })
+                .collect();
+            json!({
+                "range": token_range(text, &err.position),
+                "severity": 1,
+                "code": err.kind.code(),
+                "source": "jsona",
+                "message": err.info,
+                "relatedInformation": related,
+            })
+        })
+        .collect()
+}
+
+fn document_symbols(doc: &Document, _: &Value) -> Value {
+    Value::Array(child_symbols(doc, &doc.root))
+}
+
+fn child_symbols(doc: &Document, node: &Jsona) -> Vec<Value> {
+    match node {
+        Jsona::Array(Array { elements, .. }) => elements
+            .iter()
+            .enumerate()
+            .map(|(i, elem)| {
+                let range = doc.range(elem.get_position(), elem.get_end_position());
+                symbol(doc, &i.to_string(), elem, range.clone(), range)
+            })
+            .collect(),
+        Jsona::Object(Object { properties, .. }) => properties
+            .iter()
+            .map(|prop| {
+                let range = doc.range(&prop.position, prop.value.get_end_position());
+                let selection = token_range(doc.text, &prop.position);
+                symbol(doc, &prop.key, &prop.value, range, selection)
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn symbol(doc: &Document, name: &str, node: &Jsona, range: Value, selection: Value) -> Value {
+    // SymbolKind values of the LSP specification.
+    let kind = match node {
+        Jsona::Null(..) => 21,
+        Jsona::Boolean(..) => 17,
+        Jsona::Integer(..) | Jsona::Float(..) => 16,
+        Jsona::String(..) => 15,
+        Jsona::Array(..) => 18,
+        Jsona::Object(..) => 19,
+    };
+    let mut symbol = json!({
+        "name": name,
+        "kind": kind,
+        "range": range,
+        "selectionRange": selection,
+        "children": child_symbols(doc, node),
+    });
+    if !node.is_array() && !node.is_object() {
+        symbol["detail"] = Value::String(format_inline(node));
+    }
+    symbol
+}
+
+fn hover(doc: &Document, params: &Value) -> Value {
+    let position = match doc.parse_position(&params["position"]) {
+        Some(v) => v,
+        None => return Value::Null,
+    };
+    match doc.root.node_at(&position) {
+        Some((_, Located::Annotation(anno))) => {
+            let mut code = format!("@{}", anno.name);
+            if !anno.value.is_null() {
+                code.push_str(&format!("({})", format_inline(&anno.value)));
+            }
+            json!({
+                "contents": { "kind": "markdown", "value": format!("```jsona\n{}\n```", code) },
+                "range": doc.range(&anno.position, &anno.end),
+            })
+        }
+        _ => Value::Null,
+    }
+}
+
+fn formatting(doc: &Document, params: &Value) -> Value {
+    let options = EmitterOptions {
+        indent: params["options"]["tabSize"].as_u64().unwrap_or(4) as usize,
+    };
+    let lines = doc.text.split('\n').count();
+    let end = Position::new(0, lines, doc.line(lines).chars().count() + 1);
+    json!([{
+        "range": doc.range(&Position::default(), &end),
+        "newText": emit_with_comments(&doc.root, doc.text, &options),
+    }])
+}
+
+fn folding_ranges(doc: &Document, _: &Value) -> Value {
+    let mut ranges = Vec::new();
+    collect_folding_ranges(&doc.root, &mut ranges);
+    Value::Array(ranges)
+}
+
+fn collect_folding_ranges(node: &Jsona, ranges: &mut Vec<Value>) {
+    let children: Vec<&Jsona> = match node {
+        Jsona::Array(Array { elements, .. }) => elements.iter().collect(),
+        Jsona::Object(Object { properties, .. }) => properties.iter().map(|p| &p.value).collect(),
+        _ => return,
+    };
+    // Keep the line of the closing bracket visible.
+    let start = node.get_position().line - 1;
+    let end = node.get_end_position().line.saturating_sub(2);
+    if end > start {
+        ranges.push(json!({ "startLine": start, "endLine": end }));
+    }
+    for child in children {
+        collect_folding_ranges(child, ranges);
+    }
+}
+
+fn definition(doc: &Document, params: &Value) -> Value {
+    let position = match doc.parse_position(&params["position"]) {
+        Some(v) => v,
+        None => return Value::Null,
+    };
+    let name = match doc.root.node_at(&position) {
+        Some((_, Located::Annotation(anno))) if anno.name == USE => match anno.value.as_string() {
+            Some(v) => v.value.clone(),
+            None => return Value::Null,
+        },
+        _ => return Value::Null,
+    };
+    let (symbols, _) = resolve::collect_symbols(&doc.root);
+    match symbols
+        .get(&name)
+        .and_then(|symbol| doc.root.pointer(&symbol.path))
+    {
+        Some(node) => json!({
+            "uri": doc.uri,
+            "range": doc.range(node.get_position(), node.get_end_position()),
+        }),
+        None => Value::Null,
+    }
+}
@@ -0,0 +1,222 @@
+/// Construct a `syntax::Jsona` from a JSONA literal.
+///
+/// Annotations follow the same placement rules as the parser: an annotation right after
+/// the opening bracket belongs to the array/object, any other annotation belongs to the
+/// preceding element or property.
+///
+/// ```
+/// use jsona::jsona;
+///
+/// let node = jsona!({ @save("User")
+///     name: "foo", @upper
+///     "tags": [ @array 1, 2 ],
+///     age: 3 + 4,
+/// });
+/// assert_eq!(node.get_annotations()[0].name, "save");
+/// assert_eq!(node.key("name").unwrap().get_annotations()[0].name, "upper");
+/// assert_eq!(node.key("age").unwrap().as_integer().unwrap().value, 7);
+/// ```
+///
+/// # Panics
+///
+/// Interpolated expressions are converted with [`to_jsona`](crate::to_jsona), so the
+/// macro panics if the conversion fails, e.g. for a map with non-string keys or an integer
+/// beyond `i64`. Use `to_jsona` directly to handle that error.
+///
+/// ```should_panic
+/// use jsona::jsona;
+/// use std::collections::BTreeMap;
+///
+/// let mut map = BTreeMap::new();
+/// map.insert(vec![1], 2);
+/// jsona!({ a: map });
+/// ```
+#[macro_export]
+macro_rules! jsona {
+    ($($jsona:tt)+) => {
+        $crate::jsona_internal!($($jsona)+)
+    };
+}
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! jsona_internal {
+    //////////////////////////////////////////////////////////////////////////
+    // Annotations. Expands to an `Annotation` expression.
+    //////////////////////////////////////////////////////////////////////////
+
+    (@annotation $name:ident ()) => {
+        $crate::syntax::Annotation::new(stringify!($name), $crate::__private::Value::Null)
+    };
+
+    (@annotation $name:ident ($($value:tt)+)) => {
+        $crate::syntax::Annotation::new(stringify!($name), $crate::jsona_internal!($($value)+))
+    };
+
+    (@annotate $elems:ident $annos:ident $anno:expr) => {
+        match $elems.last_mut() {
+            Some(last) => last.get_annotations_mut().push($anno),
+            None => $annos.push($anno),
+        }
+    };
+
+    //////////////////////////////////////////////////////////////////////////
+    // Array body. Expands to statements that fill `$elems` and `$annos`.
+    //////////////////////////////////////////////////////////////////////////
+
+    (@array $elems:ident $annos:ident) => {};
+
+    (@array $elems:ident $annos:ident , $($rest:tt)*) => {
+        $crate::jsona_internal!(@array $elems $annos $($rest)*);
+    };
+
+    (@array $elems:ident $annos:ident @ $name:ident ($($value:tt)*) $($rest:tt)*) => {
+        $crate::jsona_internal!(@annotate $elems $annos
+            $crate::jsona_internal!(@annotation $name ($($value)*)));
+        $crate::jsona_internal!(@array $elems $annos $($rest)*);
+    };
+
+    (@array $elems:ident $annos:ident @ $name:ident $($rest:tt)*) => {
+        $crate::jsona_internal!(@annotate $elems $annos
+            $crate::jsona_internal!(@annotation $name ()));
+        $crate::jsona_internal!(@array $elems $annos $($rest)*);
+    };
+
+    (@array $elems:ident $annos:ident $($rest:tt)+) => {
+        $crate::jsona_internal!(@array_value $elems $annos () ($($rest)+));
+    };
+
+    // Munch the tokens of one element until `,`, `@` or the end of the array.
+    (@array_value $elems:ident $annos:ident ($($value:tt)+) ()) => {
+        $elems.push($crate::jsona_internal!($($value)+));
+    };
+
+    (@array_value $elems:ident $annos:ident ($($value:tt)+) (, $($rest:tt)*)) => {
+        $elems.push($crate::jsona_internal!($($value)+));
+        $crate::jsona_internal!(@array $elems $annos $($rest)*);
+    };
+
+    (@array_value $elems:ident $annos:ident ($($value:tt)+) (@ $($rest:tt)*)) => {
+        $elems.push($crate::jsona_internal!($($value)+));
+        $crate::jsona_internal!(@array $elems $annos @ $($rest)*);
+    };
+
+    (@array_value $elems:ident $annos:ident ($($value:tt)*) ($tt:tt $($rest:tt)*)) => {
+        $crate::jsona_internal!(@array_value $elems $annos ($($value)* $tt) ($($rest)*));
+    };
+
+    //////////////////////////////////////////////////////////////////////////
+    // Object body. Expands to statements that fill `$props` and `$annos`.
+    //////////////////////////////////////////////////////////////////////////
+
+    (@object $props:ident $annos:ident) => {};
+
+    (@object $props:ident $annos:ident , $($rest:tt)*) => {
+        $crate::jsona_internal!(@object $props $annos $($rest)*);
+    };
+
+    (@object $props:ident $annos:ident @ $name:ident ($($value:tt)*) $($rest:tt)*) => {
+        $crate::jsona_internal!(@object_annotate $props $annos
+            $crate::jsona_internal!(@annotation $name ($($value)*)));
+        $crate::jsona_internal!(@object $props $annos $($rest)*);
+    };
+
+    (@object $props:ident $annos:ident @ $name:ident $($rest:tt)*) => {
+        $crate::jsona_internal!(@object_annotate $props $annos
+            $crate::jsona_internal!(@annotation $name ()));
+        $crate::jsona_internal!(@object $props $annos $($rest)*);
+    };
+
+    (@object $props:ident $annos:ident $key:ident : $($rest:tt)+) => {
+        $crate::jsona_internal!(@object_value $props $annos (stringify!($key)) () ($($rest)+));
+    };
+
+    (@object $props:ident $annos:ident $key:literal : $($rest:tt)+) => {
+        $crate::jsona_internal!(@object_value $props $annos ($key) () ($($rest)+));
+    };
+
+    (@object $props:ident $annos:ident ($key:expr) : $($rest:tt)+) => {
+        $crate::jsona_internal!(@object_value $props $annos ($key) () ($($rest)+));
+    };
+
+    (@object_annotate $props:ident $annos:ident $anno:expr) => {
+        match $props.last_mut() {
+            Some(last) => last.value.get_annotations_mut().push($anno),
+            None => $annos.push($anno),
+        }
+    };
+
+    (@object_insert $props:ident ($key:expr) ($($value:tt)+)) => {
+        $props.push($crate::syntax::Property::new(
+            &$crate::__private::ToString::to_string(&$key),
+            $crate::jsona_internal!($($value)+),
+        ));
+    };
+
+    // Munch the tokens of one property value until `,`, `@` or the end of the object.
+    (@object_value $props:ident $annos:ident ($key:expr) ($($value:tt)+) ()) => {
+        $crate::jsona_internal!(@object_insert $props ($key) ($($value)+));
+    };
+
+    (@object_value $props:ident $annos:ident ($key:expr) ($($value:tt)+) (, $($rest:tt)*)) => {
+        $crate::jsona_internal!(@object_insert $props ($key) ($($value)+));
+        $crate::jsona_internal!(@object $props $annos $($rest)*);
+    };
+
+    (@object_value $props:ident $annos:ident ($key:expr) ($($value:tt)+) (@ $($rest:tt)*)) => {
+        $crate::jsona_internal!(@object_insert $props ($key) ($($value)+));
+        $crate::jsona_internal!(@object $props $annos @ $($rest)*);
+    };
+
+    (@object_value $props:ident $annos:ident ($key:expr) ($($value:tt)*) ($tt:tt $($rest:tt)*)) => {
+        $crate::jsona_internal!(@object_value $props $annos ($key) ($($value)* $tt) ($($rest)*));
+    };
+
+    //////////////////////////////////////////////////////////////////////////
+    // Values.
+    //////////////////////////////////////////////////////////////////////////
+
+    (null) => {
+        $crate::syntax::Jsona::from($crate::__private::Value::Null)
+    };
+
+    (true) => {
+        $crate::syntax::Jsona::from($crate::__private::Value::Bool(true))
+    };
+
+    (false) => {
+        $crate::syntax::Jsona::from($crate::__private::Value::Bool(false))
+    };
+
+    ([ $($tt:tt)* ]) => {{
+        let mut elements: $crate::__private::Vec<$crate::syntax::Jsona> = $crate::__private::Vec::new();
+        let mut annotations: $crate::__private::Vec<$crate::syntax::Annotation> = $crate::__private::Vec::new();
+        $crate::jsona_internal!(@array elements annotations $($tt)*);
+        $crate::syntax::Jsona::Array($crate::syntax::Array {
+            elements,
+            annotations,
+            position: $crate::syntax::Position::default(),
+            end: $crate::syntax::Position::default(),
+        })
+    }};
+
+    ({ $($tt:tt)* }) => {{
+        let mut properties: $crate::__private::Vec<$crate::syntax::Property> = $crate::__private::Vec::new();
+        let mut annotations: $crate::__private::Vec<$crate::syntax::Annotation> = $crate::__private::Vec::new();
+        $crate::jsona_internal!(@object properties annotations $($tt)*);
+        $crate::syntax::Jsona::Object($crate::syntax::Object {
+            properties,
+            annotations,
+            position: $crate::syntax::Position::default(),
+            end: $crate::syntax::Position::default(),
+            index: $crate::syntax::KeyIndex::default(),
+        })
+    }};
+
+    ($other:expr) => {
+        match $crate::to_jsona(&$other) {
+            Ok(value) => value,
+            Err(err) => panic!("jsona! cannot convert value: {}", err),
+        }
+    };
+}
@@ -0,0 +1,212 @@
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+use jsona::diagnostic::{ColorChoice, Renderer};
+use jsona::emitter::{emit, emit_with_comments, EmitterOptions};
+use jsona::source::SourceMap;
+use jsona::Jsona;
+use serde_json::Value;
+
+const USAGE: &str = "usage: jsona <command> [args]
+
+commands:
+    check <file>...             validate files, printing diagnostics
+    fmt [--check] [<file>...]   format files in place, or list the unformatted ones with --check
+    to-json [<file>]            convert JSONA to JSON
+    from-json [<file>]          convert JSON to JSONA
+    get <pointer> [<file>]      print the node at a JSON pointer as JSON
+    lsp                         run the language server on stdio
+
+Files default to stdin, which can also be given as '-'.";
+
+const STDIN: &str = "-";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let code = match run(&args) {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("{}", message);
+            2
+        }
+    };
+    process::exit(code);
+}
+
+fn run(args: &[String]) -> Result<i32, String> {
+    let (command, args) = match args.split_first() {
+        Some(v) => v,
+        None => return Err(USAGE.into()),
+    };
+    match command.as_str() {
+        "check" => check(args),
+        "fmt" => fmt(args),
+        "to-json" => to_json(args),
+        "from-json" => from_json(args),
+        "get" => get(args),
+        "lsp" => lsp(args),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            Ok(0)
+        }
+        _ => Err(format!("unknown command '{}'\n\n{}", command, USAGE)),
+    }
+}
+
+fn check(args: &[String]) -> Result<i32, String> {
+    let mut sources = SourceMap::new();
+    let mut failed = false;
+    for path in files(args)? {
+        let source = read(&path)?;
+        let mut doc = match parse(&mut sources, &path, &source) {
+            Some(doc) => doc,
+            None => {
+                failed = true;
+                continue;
+            }
+        };
+        if let Err(errors) = jsona::resolve::resolve(&mut doc) {
+            for err in errors {
+                report(&sources, &err);
+            }
+            failed = true;
+        }
+    }
+    Ok(failed as i32)
+}
+
+fn fmt(args: &[String]) -> Result<i32, String> {
+    let (check, args): (Vec<&String>, Vec<&String>) = args.iter().partition(|v| *v == "--check");
+    let check = !check.is_empty();
+    let paths: Vec<String> = args.into_iter().cloned().collect();
+    let mut sources = SourceMap::new();
+    let mut failed = false;
+    for path in files(&paths)? {
+        let source = read(&path)?;
+        let doc = match parse(&mut sources, &path, &source) {
+            Some(doc) => doc,
+            None => {
+                failed = true;
+                continue;
+            }
+        };
+        let output = emit_with_comments(&doc, &source, &EmitterOptions::default());
+        if path == STDIN {
+            if check {
+                failed |= output != source;
+            } else {
+                print!("{}", output);
+            }
+        } else if output != source {
+            if check {
+                println!("{}", path);
+                failed = true;
+            } else {
+                fs::write(&path, output).map_err(|e| format!("cannot write {}, {}", path, e))?;
+            }
+        }
+    }
+    Ok(failed as i32)
+}
+
+fn to_json(args: &[String]) -> Result<i32, String> {
+    let path = single_file(args)?;
+    let doc = match parse(&mut SourceMap::new(), &path, &read(&path)?) {
+        Some(doc) => doc,
+        None => return Ok(1),
+    };
+    print_json(&Value::from(&doc));
+    Ok(0)
+}
+
+fn from_json(args: &[String]) -> Result<i32, String> {
+    let path = single_file(args)?;
+    let value: Value = serde_json::from_str(&read(&path)?)
+        .map_err(|e| format!("{}: invalid JSON, {}", path, e))?;
+    print!("{}", emit(&Jsona::from(value), &EmitterOptions::default()));
+    Ok(0)
+}
+
+fn get(args: &[String]) -> Result<i32, String> {
+    let (pointer, args) = match args.split_first() {
+        Some(v) => v,
+        None => return Err(USAGE.into()),
+    };
+    let path = single_file(args)?;
+    let doc = match parse(&mut SourceMap::new(), &path, &read(&path)?) {
+        Some(doc) => doc,
+        None => return Ok(1),
+    };
+    match doc.pointer(pointer) {
+        Some(node) => {
+            print_json(&Value::from(node));
+            Ok(0)
+        }
+        None => {
+            eprintln!("no node at '{}'", pointer);
+            Ok(1)
+        }
+    }
+}
+
+fn lsp(args: &[String]) -> Result<i32, String> {
+    if !args.is_empty() {
+        return Err(USAGE.into());
+    }
+    let stdin = io::stdin();
+    jsona::lsp::run(stdin.lock(), io::stdout()).map_err(|e| format!("lsp failed, {}", e))?;
+    Ok(0)
+}
+
+fn files(args: &[String]) -> Result<Vec<String>, String> {
+    if let Some(flag) = args.iter().find(|v| v.starts_with("--")) {
+        return Err(format!("unknown flag '{}'\n\n{}", flag, USAGE));
+    }
+    if args.is_empty() {
+        Ok(vec![STDIN.to_string()])
+    } else {
+        Ok(args.to_vec())
+    }
+}
+
+fn single_file(args: &[String]) -> Result<String, String> {
+    let mut files = files(args)?;
+    if files.len() > 1 {
+        return Err(format!("expected a single file\n\n{}", USAGE));
+    }
+    Ok(files.remove(0))
+}
+
+fn read(path: &str) -> Result<String, String> {
+    if path == STDIN {
+        let mut source = String::new();
+        io::stdin()
+            .read_to_string(&mut source)
+            .map_err(|e| format!("cannot read stdin, {}", e))?;
+        Ok(source)
+    } else {
+        fs::read_to_string(path).map_err(|e| format!("cannot read {}, {}", path, e))
+    }
+}
+
+/// Parse `source`, reporting the error if any.
+fn parse(sources: &mut SourceMap, path: &str, source: &str) -> Option<Jsona> {
+    let name = if path == STDIN { "<stdin>" } else { path };
+    let id = sources.add(name, source);
+    match sources.parse(id) {
+        Ok(doc) => Some(doc),
+        Err(err) => {
+            report(sources, &err);
+            None
+        }
+    }
+}
+
+fn report(sources: &SourceMap, err: &jsona::Error) {
+    eprint!("{}", Renderer::new(ColorChoice::Auto).render(sources, err));
+}
+
+fn print_json(value: &Value) {
+    println!("{}", serde_json::to_string_pretty(value).unwrap());
+}
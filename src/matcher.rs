@@ -0,0 +1,246 @@
+//! Matching of actual data against an annotated JSONA expectation, as in API tests.
+//!
+//! By default values have to be equal, objects need the same keys and arrays the same length.
+//! Annotations relax this:
+//!
+//! - `@partial` on an object allows extra keys, on an array extra trailing items.
+//! - `@every` on an array matches each actual item against the element of the expectation, or
+//!   against any of them if there are several.
+//! - `@type` only checks the type, that of the expected node or a named one as in
+//!   `@type("integer")`.
+//! - `@eval("...")` matches anything; register a handler to evaluate predicates.
+//!
+//! ```
+//! use jsona::matcher::match_value;
+//! use serde_json::json;
+//!
+//! let expected = jsona::from_str(r#"{ @partial id: 0, @type name: "bob" }"#).unwrap();
+//! let mismatches = match_value(&expected, &json!({ "id": "x", "name": "bob", "age": 3 }));
+//! assert_eq!(mismatches[0].to_string(), "'/id' expected type integer, got string");
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::pointer;
+use crate::schema::{is_type, schema_type, type_name};
+use crate::syntax::{Annotation, Array, Jsona, Object, Position};
+
+pub const PARTIAL: &str = "partial";
+pub const EVERY: &str = "every";
+pub const TYPE: &str = "type";
+pub const EVAL: &str = "eval";
+
+/// A difference between the expectation and the actual value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mismatch {
+    /// JSON pointer of the value in the actual data.
+    pub path: String,
+    /// Position of the expected node.
+    pub position: Position,
+    pub message: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "'{}' {}", self.path, self.message)
+    }
+}
+
+/// What an annotation handler decided about a node.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Verdict {
+    /// The node matches, its value is not compared.
+    Match,
+    Mismatch(String),
+    /// Leave the decision to other annotations and the default comparison.
+    Continue,
+}
+
+/// Decides matches for nodes carrying a certain annotation.
+pub trait AnnotationHandler {
+    fn check(&self, annotation: &Annotation, expected: &Jsona, actual: &Value) -> Verdict;
+}
+
+impl<F> AnnotationHandler for F
+where
+    F: Fn(&Annotation, &Jsona, &Value) -> Verdict,
+{
+    fn check(&self, annotation: &Annotation, expected: &Jsona, actual: &Value) -> Verdict {
+        self(annotation, expected, actual)
+    }
+}
+
+/// Match `actual` against `expected` with the default handlers, reporting all mismatches.
+pub fn match_value(expected: &Jsona, actual: &Value) -> Vec<Mismatch> {
+    Matcher::new().match_value(expected, actual)
+}
+
+/// A matching engine with handlers per annotation name.
+pub struct Matcher {
+    handlers: HashMap<String, Box<dyn AnnotationHandler>>,
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Matcher {
+    /// A matcher with handlers for `@type` and `@eval`.
+    pub fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+        }
+        .with_handler(TYPE, check_type)
+        .with_handler(EVAL, |_: &Annotation, _: &Jsona, _: &Value| Verdict::Match)
+    }
+    /// Handle annotation `name` with `handler`, replacing any previous handler.
+    pub fn with_handler<H: AnnotationHandler + 'static>(mut self, name: &str, handler: H) -> Self {
+        self.handlers.insert(name.to_string(), Box::new(handler));
+        self
+    }
+    pub fn match_value(&self, expected: &Jsona, actual: &Value) -> Vec<Mismatch> {
+        let mut mismatches = Vec::new();
+        self.match_node(expected, actual, &mut Vec::new(), &mut mismatches);
+        mismatches
+    }
+    fn match_node(
+        &self,
+        expected: &Jsona,
+        actual: &Value,
+        path: &mut Vec<String>,
+        mismatches: &mut Vec<Mismatch>,
+    ) {
+        let mut report = |path: &[String], message: String| {
+            mismatches.push(Mismatch {
+                path: pointer::join(path),
+                position: *expected.get_position(),
+                message,
+            })
+        };
+        for anno in expected.get_annotations() {
+            let handler = match self.handlers.get(&anno.name) {
+                Some(v) => v,
+                None => continue,
+            };
+            match handler.check(anno, expected, actual) {
+                Verdict::Match => return,
+                Verdict::Mismatch(message) => return report(path, message),
+                Verdict::Continue => {}
+            }
+        }
+        match (expected, actual) {
+            (Jsona::Object(Object { properties, .. }), Value::Object(map)) => {
+                for prop in properties {
+                    path.push(prop.key.clone());
+                    match map.get(&prop.key) {
+                        Some(v) => self.match_node(&prop.value, v, path, mismatches),
+                        None => mismatches.push(Mismatch {
+                            path: pointer::join(path),
+                            position: prop.position,
+                            message: "is missing".into(),
+                        }),
+                    }
+                    path.pop();
+                }
+                if !expected.has_annotation(PARTIAL) {
+                    for key in map.keys() {
+                        if properties.iter().all(|p| &p.key != key) {
+                            path.push(key.clone());
+                            mismatches.push(Mismatch {
+                                path: pointer::join(path),
+                                position: *expected.get_position(),
+                                message: "is not expected".into(),
+                            });
+                            path.pop();
+                        }
+                    }
+                }
+            }
+            (Jsona::Array(Array { elements, .. }), Value::Array(items)) => {
+                if expected.has_annotation(EVERY) {
+                    for (i, item) in items.iter().enumerate() {
+                        path.push(i.to_string());
+                        self.match_any(elements, item, path, mismatches);
+                        path.pop();
+                    }
+                    return;
+                }
+                let partial = expected.has_annotation(PARTIAL);
+                if items.len() < elements.len() || (!partial && items.len() > elements.len()) {
+                    report(
+                        path,
+                        format!(
+                            "expected {}{} items, got {}",
+                            if partial { "at least " } else { "" },
+                            elements.len(),
+                            items.len()
+                        ),
+                    );
+                }
+                for (i, (elem, item)) in elements.iter().zip(items.iter()).enumerate() {
+                    path.push(i.to_string());
+                    self.match_node(elem, item, path, mismatches);
+                    path.pop();
+                }
+            }
+            (Jsona::Object(..), _) | (Jsona::Array(..), _) => report(
+                path,
+                format!(
+                    "expected type {}, got {}",
+                    schema_type(expected),
+                    type_name(actual)
+                ),
+            ),
+            _ => {
+                let value = Value::from(expected);
+                if &value != actual {
+                    report(path, format!("expected {}, got {}", value, actual));
+                }
+            }
+        }
+    }
+    /// Match `actual` against one of `elements`, all of them when empty.
+    fn match_any(
+        &self,
+        elements: &[Jsona],
+        actual: &Value,
+        path: &mut Vec<String>,
+        mismatches: &mut Vec<Mismatch>,
+    ) {
+        let mut first = None;
+        for elem in elements {
+            let mut found = Vec::new();
+            self.match_node(elem, actual, path, &mut found);
+            if found.is_empty() {
+                return;
+            }
+            first.get_or_insert(found);
+        }
+        match first {
+            Some(found) if elements.len() == 1 => mismatches.extend(found),
+            Some(_) => mismatches.push(Mismatch {
+                path: pointer::join(path),
+                position: *elements[0].get_position(),
+                message: format!("matches none of the {} expected items", elements.len()),
+            }),
+            None => {}
+        }
+    }
+}
+
+fn check_type(anno: &Annotation, expected: &Jsona, actual: &Value) -> Verdict {
+    let name = match &anno.value {
+        Jsona::String(v) => v.value.as_str(),
+        _ => schema_type(expected),
+    };
+    if is_type(name, actual) {
+        Verdict::Match
+    } else {
+        Verdict::Mismatch(format!("expected type {}, got {}", name, type_name(actual)))
+    }
+}
@@ -0,0 +1,273 @@
+//! Generation of mock data from a JSONA template.
+//!
+//! Values are copied from the template unless annotations direct a generator:
+//!
+//! - `@enum([..])` picks one of the values.
+//! - `@mock("kind")` generates a value of a kind: `name`, `word`, `sentence`, `email`, `url`,
+//!   `uuid`, `ipv4`, `date`, `time`, `date-time`, `boolean`, `integer`, `number` or `string`.
+//!   Without an argument the kind follows the type of the node.
+//! - `@range([min, max])` bounds generated numbers, inclusive; a template number annotated with
+//!   it alone is generated too.
+//! - `@repeat(n)` or `@repeat([min, max])` on an array makes that many items, cycling through
+//!   the template elements. Counts are capped at [`MAX_REPEAT`].
+//!
+//! The output only depends on the template and the seed. Resolve `@use` references first.
+//! Generation ignores arguments it can not use, such as `@range([3, 1])` or an unknown
+//! `@mock` kind; validate with the signatures from [`register`] to report them.
+//!
+//! ```
+//! let doc = jsona::from_str(r#"{ id: 0, @range([1, 9]) tags: [ @repeat(2) "", @mock("word") ] }"#)
+//!     .unwrap();
+//! let value = jsona::mock::mock(&doc, 42);
+//! assert_eq!(value, jsona::mock::mock(&doc, 42));
+//! assert_eq!(value["tags"].as_array().unwrap().len(), 2);
+//! ```
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{Map, Value};
+
+use crate::registry::{AnnotationRegistry, AnnotationSignature, ArgumentSchema, Target};
+use crate::schema::ENUM;
+use crate::syntax::{Array, Jsona, Object};
+
+pub const MOCK: &str = "mock";
+pub const RANGE: &str = "range";
+pub const REPEAT: &str = "repeat";
+
+/// The most items `@repeat` makes in one array.
+pub const MAX_REPEAT: u64 = 10_000;
+
+/// The kinds `@mock` accepts.
+pub const KINDS: [&str; 14] = [
+    "name",
+    "word",
+    "sentence",
+    "email",
+    "url",
+    "uuid",
+    "ipv4",
+    "date",
+    "time",
+    "date-time",
+    "boolean",
+    "integer",
+    "number",
+    "string",
+];
+
+const WORDS: [&str; 16] = [
+    "alpha", "bravo", "delta", "echo", "fox", "gold", "harbor", "island", "jade", "kite", "lemon",
+    "maple", "north", "ocean", "pine", "river",
+];
+const NAMES: [&str; 12] = [
+    "Alice", "Bob", "Carol", "Dave", "Erin", "Frank", "Grace", "Heidi", "Ivan", "Judy", "Mallory",
+    "Oscar",
+];
+const DOMAINS: [&str; 4] = ["example.com", "example.org", "example.net", "test.dev"];
+
+/// Add the signatures of the mock annotations to `registry`.
+pub fn register(registry: &mut AnnotationRegistry) {
+    let kinds = KINDS.iter().map(|v| v.to_string()).collect();
+    registry
+        .register(AnnotationSignature::new(
+            MOCK,
+            ArgumentSchema::Optional(Box::new(ArgumentSchema::Keyword(kinds))),
+        ))
+        .register(
+            AnnotationSignature::new(
+                RANGE,
+                ArgumentSchema::Bounds(Box::new(ArgumentSchema::Number)),
+            )
+            .with_targets(&[Target::Scalar]),
+        )
+        .register(
+            AnnotationSignature::new(
+                REPEAT,
+                ArgumentSchema::OneOf(vec![
+                    ArgumentSchema::Integer,
+                    ArgumentSchema::Bounds(Box::new(ArgumentSchema::Integer)),
+                ]),
+            )
+            .with_targets(&[Target::Array]),
+        );
+}
+
+/// Generate data from `doc` with a generator seeded with `seed`.
+pub fn mock(doc: &Jsona, seed: u64) -> Value {
+    Mocker::new(seed).mock(doc)
+}
+
+/// A mock data generator; successive calls continue the random sequence.
+#[derive(Debug, Clone)]
+pub struct Mocker {
+    state: u64,
+}
+
+impl Mocker {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    /// A generator seeded from the clock.
+    pub fn random() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|v| v.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::new(nanos)
+    }
+    pub fn mock(&mut self, doc: &Jsona) -> Value {
+        if let Ok(Some(values)) = doc.annotation_as::<Vec<Value>>(ENUM) {
+            if !values.is_empty() {
+                let i = self.below(values.len() as u64) as usize;
+                return values[i].clone();
+            }
+        }
+        let range = match doc.annotation_as::<(f64, f64)>(RANGE) {
+            Ok(Some((min, max))) if min <= max => Some((min, max)),
+            _ => None,
+        };
+        if let Some(anno) = doc.annotation(MOCK) {
+            let kind = match &anno.value {
+                Jsona::String(v) => Some(v.value.as_str()),
+                _ => kind_of(doc),
+            };
+            if let Some(value) = kind.and_then(|kind| self.generate(kind, range)) {
+                return value;
+            }
+        }
+        match doc {
+            Jsona::Object(Object { properties, .. }) => {
+                let mut map = Map::new();
+                for prop in properties {
+                    map.insert(prop.key.clone(), self.mock(&prop.value));
+                }
+                Value::Object(map)
+            }
+            Jsona::Array(Array { elements, .. }) => {
+                let count = match doc.annotation_as::<Value>(REPEAT) {
+                    Ok(Some(Value::Number(n))) => n.as_u64().map(|n| n.min(MAX_REPEAT)),
+                    Ok(Some(Value::Array(bounds))) => match bounds.as_slice() {
+                        [min, max] => match (min.as_u64(), max.as_u64()) {
+                            (Some(min), Some(max)) if min <= max => {
+                                let (min, max) = (min.min(MAX_REPEAT), max.min(MAX_REPEAT));
+                                Some(min + self.below(max - min + 1))
+                            }
+                            _ => None,
+                        },
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                match count {
+                    Some(count) if !elements.is_empty() => (0..count as usize)
+                        .map(|i| self.mock(&elements[i % elements.len()]))
+                        .collect(),
+                    _ => elements.iter().map(|v| self.mock(v)).collect(),
+                }
+            }
+            Jsona::Integer(..) | Jsona::Float(..) if range.is_some() => kind_of(doc)
+                .and_then(|kind| self.generate(kind, range))
+                .unwrap_or_else(|| Value::from(doc)),
+            _ => Value::from(doc),
+        }
+    }
+    /// A value of `kind`, `None` for unknown kinds.
+    fn generate(&mut self, kind: &str, range: Option<(f64, f64)>) -> Option<Value> {
+        let value = match kind {
+            "name" => self.pick(&NAMES).into(),
+            "word" | "string" => self.pick(&WORDS).into(),
+            "sentence" => {
+                let count = 4 + self.below(5) as usize;
+                let words: Vec<_> = (0..count).map(|_| self.pick(&WORDS)).collect();
+                let sentence = words.join(" ");
+                format!("{}{}.", sentence[..1].to_uppercase(), &sentence[1..]).into()
+            }
+            "email" => format!(
+                "{}.{}@{}",
+                self.pick(&NAMES).to_lowercase(),
+                self.pick(&WORDS),
+                self.pick(&DOMAINS)
+            )
+            .into(),
+            "url" => format!("https://{}/{}", self.pick(&DOMAINS), self.pick(&WORDS)).into(),
+            "uuid" => {
+                let (a, b) = (self.next(), self.next());
+                format!(
+                    "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+                    a >> 32,
+                    (a >> 16) & 0xffff,
+                    a & 0xfff,
+                    0x8000 | ((b >> 48) & 0x3fff),
+                    b & 0xffff_ffff_ffff
+                )
+                .into()
+            }
+            "ipv4" => {
+                let parts: Vec<_> = (0..4).map(|_| (1 + self.below(254)).to_string()).collect();
+                parts.join(".").into()
+            }
+            "date" => self.date().into(),
+            "time" => self.time().into(),
+            "date-time" => format!("{}T{}Z", self.date(), self.time()).into(),
+            "boolean" => (self.below(2) == 1).into(),
+            "integer" => {
+                let (min, max) = range.unwrap_or((0.0, 1000.0));
+                let (min, max) = (min.ceil() as i64, max.floor() as i64);
+                if min > max {
+                    return None;
+                }
+                let span = (max as i128 - min as i128 + 1) as u128;
+                ((min as i128 + (self.next() as u128 % span) as i128) as i64).into()
+            }
+            "number" => {
+                let (min, max) = range.unwrap_or((0.0, 1000.0));
+                let unit = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+                (min + unit * (max - min)).into()
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+    fn date(&mut self) -> String {
+        format!(
+            "{}-{:02}-{:02}",
+            2000 + self.below(30),
+            1 + self.below(12),
+            1 + self.below(28)
+        )
+    }
+    fn time(&mut self) -> String {
+        format!(
+            "{:02}:{:02}:{:02}",
+            self.below(24),
+            self.below(60),
+            self.below(60)
+        )
+    }
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+    /// SplitMix64.
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// The generator kind for the type of `node`, `None` for containers.
+fn kind_of(node: &Jsona) -> Option<&'static str> {
+    match node {
+        Jsona::Boolean(..) => Some("boolean"),
+        Jsona::Integer(..) => Some("integer"),
+        Jsona::Float(..) => Some("number"),
+        Jsona::Null(..) | Jsona::String(..) => Some("string"),
+        Jsona::Array(..) | Jsona::Object(..) => None,
+    }
+}
@@ -1,4 +1,4 @@
-use crate::error::Error;
+use crate::error::{Error, ErrorKind};
 
 use crate::syntax::Position;
 use crate::lexer::{Lexer, Token, TokenKind};
@@ -22,19 +22,36 @@ pub trait EventReceiver {
     fn on_event(&mut self, event: Event, position: Position);
 }
 
+/// Like `EventReceiver`, but also receives the position right after the token(s) of the event.
+pub trait SpannedEventReceiver {
+    fn on_event(&mut self, event: Event, position: Position, end: Position);
+}
+
+impl<R: EventReceiver> SpannedEventReceiver for R {
+    fn on_event(&mut self, event: Event, position: Position, _end: Position) {
+        EventReceiver::on_event(self, event, position)
+    }
+}
+
 pub type ParseResult<T> = Result<T, Error>;
 
+/// A limit of nested arrays and objects suitable for untrusted input. Parsing is unlimited
+/// unless [`Parser::with_max_depth`] sets a limit.
+pub const MAX_DEPTH: usize = 128;
+
 pub struct Parser<T> {
     scanner: Lexer<T>,
     buf: Option<Token>,
     annotation_scope: bool,
+    depth: usize,
+    max_depth: usize,
 }
 
 fn sanitize_token(tok: Token) -> ParseResult<Token> {
-    if let TokenKind::LexError(message) = tok.kind {
-        return Err(Error::new(message, tok.position));
+    if let TokenKind::LexError(kind, message) = tok.kind {
+        return Err(Error::new(message, tok.position).with_kind(kind));
     }
-    return Ok(tok);
+    Ok(tok)
 }
 
 impl<T: Iterator<Item = char>> Parser<T> {
@@ -43,8 +60,15 @@ impl<T: Iterator<Item = char>> Parser<T> {
             scanner: Lexer::new(input),
             buf: None,
             annotation_scope: false,
+            depth: 0,
+            max_depth: usize::MAX,
         }
     }
+    /// Limit the nesting of arrays and objects, deeper input is rejected.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
     fn peek_token(&mut self) -> ParseResult<Token> {
         if let Some(tok) = self.buf.clone() {
             return Ok(tok.clone());
@@ -54,7 +78,7 @@ impl<T: Iterator<Item = char>> Parser<T> {
             self.buf = Some(tok.clone());
             return Ok(tok);
         }
-        return Err(Error::abort());
+        Err(Error::abort())
     }
     fn next_token(&mut self) -> ParseResult<Token> {
         if let Some(tok) = self.buf.take() {
@@ -65,7 +89,7 @@ impl<T: Iterator<Item = char>> Parser<T> {
         }
         Err(Error::abort())
     }
-    pub fn parse<R: EventReceiver>(&mut self, recv: &mut R) -> ParseResult<()> {
+    pub fn parse<R: SpannedEventReceiver>(&mut self, recv: &mut R) -> ParseResult<()> {
         self.parse_node(recv)?;
         let tok = self.peek_token()?;
         if let TokenKind::Eof = tok.kind {
@@ -76,16 +100,20 @@ impl<T: Iterator<Item = char>> Parser<T> {
             Err(Error::unexpect(tok, None))
         }
     }
-    fn parse_node<R: EventReceiver>(&mut self, recv: &mut R) -> ParseResult<()> {
+    fn parse_node<R: SpannedEventReceiver>(&mut self, recv: &mut R) -> ParseResult<()> {
         let tok = self.next_token()?;
         match tok.kind {
             TokenKind::LeftBrace => {
-                recv.on_event(Event::ObjectStart, tok.position);
-                self.parse_object(recv)?;
+                self.enter(&tok)?;
+                recv.on_event(Event::ObjectStart, tok.position, self.scanner.position());
+                self.parse_object(recv, tok.position)?;
+                self.depth -= 1;
             }
             TokenKind::LeftBracket => {
-                recv.on_event(Event::ArrayStart, tok.position);
-                self.parse_array(recv)?;
+                self.enter(&tok)?;
+                recv.on_event(Event::ArrayStart, tok.position, self.scanner.position());
+                self.parse_array(recv, tok.position)?;
+                self.depth -= 1;
             }
             TokenKind::Identifier(v) => {
                 let ev = {
@@ -94,61 +122,65 @@ impl<T: Iterator<Item = char>> Parser<T> {
                         "false" => Event::Boolean(false),
                         "null" => Event::Null,
                         _ => {
+                            let expected = vec!["true".into(), "false".into(), "null".into()];
                             return Err(Error::new(
                                 format!("unexpect identifier \"{}\"", v),
                                 tok.position,
-                            ))
+                            )
+                            .with_kind(ErrorKind::UnexpectedToken { expected }));
                         }
                     }
                 };
-                recv.on_event(ev, tok.position);
+                recv.on_event(ev, tok.position, self.scanner.position());
             }
             TokenKind::IntegerLiteral(i) => {
-                recv.on_event(Event::Integer(i), tok.position);
+                recv.on_event(Event::Integer(i), tok.position, self.scanner.position());
             }
             TokenKind::FloatLiteral(f) => {
-                recv.on_event(Event::Float(f), tok.position);
+                recv.on_event(Event::Float(f), tok.position, self.scanner.position());
             }
             TokenKind::StringLiteral(s) => {
-                recv.on_event(Event::String(s), tok.position);
+                recv.on_event(Event::String(s), tok.position, self.scanner.position());
             }
             _ => return Err(Error::unexpect(tok, None)),
         };
         Ok(())
     }
-    fn parse_array<R: EventReceiver>(&mut self, recv: &mut R) -> ParseResult<()> {
+    fn enter(&mut self, tok: &Token) -> ParseResult<()> {
+        self.depth += 1;
+        if self.depth > self.max_depth {
+            return Err(Error::new(
+                format!("nesting depth exceeds the limit of {}", self.max_depth),
+                tok.position,
+            )
+            .with_kind(ErrorKind::LimitExceeded));
+        }
+        Ok(())
+    }
+    fn parse_array<R: SpannedEventReceiver>(
+        &mut self,
+        recv: &mut R,
+        start: Position,
+    ) -> ParseResult<()> {
         self.parse_annotaions(recv)?;
         let mut allow_comma = false;
         let mut no_elem = true;
         loop {
             let tok = self.peek_token()?;
             match tok.kind {
-                TokenKind::Comma => {
-                    if allow_comma {
-                        self.next_token()?;
-                        allow_comma = false;
-                    } else {
-                        return Err(Error::unexpect(tok, None));
-                    }
+                TokenKind::Comma if allow_comma => {
+                    self.next_token()?;
+                    allow_comma = false;
                 }
                 TokenKind::RightBracket => {
-                    recv.on_event(Event::ArrayStop, tok.position);
+                    recv.on_event(Event::ArrayStop, tok.position, self.scanner.position());
                     self.next_token()?;
                     break;
                 }
                 TokenKind::At => {
                     self.parse_annotaions(recv)?;
-                    let allow_annotations = {
-                        if !allow_comma {
-                            true
-                        } else {
-                            if let TokenKind::RightBracket = self.peek_token()?.kind {
-                                true
-                            } else {
-                                false
-                            }
-                        }
-                    };
+                    let allow_annotations =
+                        !allow_comma || matches!(self.peek_token()?.kind, TokenKind::RightBracket);
                     if !allow_annotations || no_elem {
                         return Err(Error::unexpect(tok, None));
                     }
@@ -162,44 +194,43 @@ impl<T: Iterator<Item = char>> Parser<T> {
                         return Err(Error::expect(&[TokenKind::Comma], tok, "array".into()));
                     }
                 }
+                TokenKind::Eof => {
+                    let expected: &[&str] = if allow_comma {
+                        &[",", "]"]
+                    } else {
+                        &["]", "value"]
+                    };
+                    return Err(unclosed(tok, start, "array", expected));
+                }
                 _ => return Err(Error::unexpect(tok, None)),
             }
         }
         Ok(())
     }
-    fn parse_object<R: EventReceiver>(&mut self, recv: &mut R) -> ParseResult<()> {
+    fn parse_object<R: SpannedEventReceiver>(
+        &mut self,
+        recv: &mut R,
+        start: Position,
+    ) -> ParseResult<()> {
         self.parse_annotaions(recv)?;
         let mut allow_comma = false;
         let mut no_kv = true;
         loop {
             let tok = self.peek_token()?;
             match tok.kind {
-                TokenKind::Comma => {
-                    if allow_comma {
-                        self.next_token()?;
-                        allow_comma = false;
-                    } else {
-                        return Err(Error::unexpect(tok, None));
-                    }
+                TokenKind::Comma if allow_comma => {
+                    self.next_token()?;
+                    allow_comma = false;
                 }
                 TokenKind::RightBrace => {
-                    recv.on_event(Event::ObjectStop, tok.position);
+                    recv.on_event(Event::ObjectStop, tok.position, self.scanner.position());
                     self.next_token()?;
                     break;
                 }
                 TokenKind::At => {
                     self.parse_annotaions(recv)?;
-                    let allow_annotations = {
-                        if !allow_comma {
-                            true
-                        } else {
-                            if let TokenKind::RightBrace = self.peek_token()?.kind {
-                                true
-                            } else {
-                                false
-                            }
-                        }
-                    };
+                    let allow_annotations =
+                        !allow_comma || matches!(self.peek_token()?.kind, TokenKind::RightBrace);
                     if !allow_annotations || no_kv {
                         return Err(Error::unexpect(tok, None));
                     }
@@ -209,7 +240,7 @@ impl<T: Iterator<Item = char>> Parser<T> {
                 | TokenKind::IntegerLiteral(..) => {
                     let tok = self.next_token()?;
                     let key = tok.get_value().unwrap();
-                    recv.on_event(Event::String(key), tok.position);
+                    recv.on_event(Event::String(key), tok.position, self.scanner.position());
                     let tok = self.peek_token()?;
                     match tok.kind {
                         TokenKind::Colon => {
@@ -232,12 +263,20 @@ impl<T: Iterator<Item = char>> Parser<T> {
                         }
                     }
                 }
+                TokenKind::Eof => {
+                    let expected: &[&str] = if allow_comma {
+                        &[",", "}"]
+                    } else {
+                        &["}", "key"]
+                    };
+                    return Err(unclosed(tok, start, "object", expected));
+                }
                 _ => return Err(Error::unexpect(tok, None)),
             }
         }
         Ok(())
     }
-    fn parse_annotaions<R: EventReceiver>(&mut self, recv: &mut R) -> ParseResult<()> {
+    fn parse_annotaions<R: SpannedEventReceiver>(&mut self, recv: &mut R) -> ParseResult<()> {
         let tok = self.peek_token()?;
         if let TokenKind::At = tok.kind {
             if self.annotation_scope {
@@ -247,7 +286,8 @@ impl<T: Iterator<Item = char>> Parser<T> {
             let tok2 = self.peek_token()?;
             if let TokenKind::Identifier(key) = tok2.kind {
                 self.next_token()?;
-                recv.on_event(Event::AnnotationStart(key), tok2.position);
+                let name_end = self.scanner.position();
+                recv.on_event(Event::AnnotationStart(key), tok2.position, name_end);
                 let tok3 = self.peek_token()?;
                 if let TokenKind::LeftParen = tok3.kind {
                     self.next_token()?;
@@ -255,16 +295,16 @@ impl<T: Iterator<Item = char>> Parser<T> {
                     self.parse_node(recv)?;
                     let tok4 = self.next_token()?;
                     self.annotation_scope = false;
-                    recv.on_event(Event::AnnotationEnd, tok4.position);
+                    recv.on_event(Event::AnnotationEnd, tok4.position, self.scanner.position());
                     self.parse_annotaions(recv)?;
                 } else {
-                    recv.on_event(Event::Null, tok2.position);
-                    recv.on_event(Event::AnnotationEnd, tok2.position);
+                    recv.on_event(Event::Null, tok2.position, name_end);
+                    recv.on_event(Event::AnnotationEnd, tok2.position, name_end);
                     self.parse_annotaions(recv)?;
                 }
             } else {
                 return Err(Error::expect(
-                    &[TokenKind::Identifier("identifer".into())],
+                    &[TokenKind::Identifier("identifier".into())],
                     tok2,
                     "annotation".into(),
                 ));
@@ -273,3 +313,11 @@ impl<T: Iterator<Item = char>> Parser<T> {
         Ok(())
     }
 }
+
+/// Error for a container that reaches eof before its closing bracket.
+fn unclosed(tok: Token, start: Position, container: &str, expected: &[&str]) -> Error {
+    let expected = expected.iter().map(|v| v.to_string()).collect();
+    Error::unexpect(tok, None)
+        .with_kind(ErrorKind::UnexpectedToken { expected })
+        .with_label(start, &format!("{} opened here", container))
+}
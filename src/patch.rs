@@ -0,0 +1,219 @@
+//! JSON Patch (RFC 6902) and JSON Merge Patch (RFC 7396) applied to `Jsona`.
+//!
+//! Nodes the patch does not touch keep their annotations and positions. A node whose value is
+//! replaced keeps its annotations; a node moved or copied onto it brings along only those
+//! whose names the target does not have.
+//!
+//! `test` compares values as [`serde_json::Value`], so `1` and `1.0` are not equal.
+//!
+//! JSON Patch operations may carry an extra `annotation` member to operate on the annotation of
+//! that name instead of the value, e.g. `{"op": "add", "path": "/port", "annotation": "min",
+//! "value": 1024}`. Such operations support `add`, `remove`, `replace` and `test`.
+
+use serde_json::{Map, Value};
+
+use crate::error::{Error, ErrorKind};
+use crate::pointer;
+use crate::syntax::{Annotation, Array, Jsona, Position};
+
+type PatchResult<T> = Result<T, String>;
+
+/// Apply a JSON Patch. The document is left untouched if any operation fails.
+pub fn patch(doc: &mut Jsona, patch: &Value) -> Result<(), Error> {
+    let ops = patch
+        .as_array()
+        .ok_or_else(|| {
+            Error::new("patch must be an array".into(), Position::default())
+                .with_kind(ErrorKind::Patch)
+        })?;
+    let mut target = doc.clone();
+    for (i, op) in ops.iter().enumerate() {
+        apply_operation(&mut target, op).map_err(|e| {
+            Error::new(
+                format!("patch operation {} failed, {}", i, e),
+                Position::default(),
+            )
+            .with_kind(ErrorKind::Patch)
+        })?;
+    }
+    *doc = target;
+    Ok(())
+}
+
+/// Apply a JSON Merge Patch.
+pub fn merge_patch(doc: &mut Jsona, patch: &Value) {
+    match patch {
+        Value::Object(map) => {
+            if !doc.is_object() {
+                replace(doc, Jsona::from(Value::Object(Map::new())));
+            }
+            if let Jsona::Object(object) = doc {
+                for (key, value) in map {
+                    match (object.get_mut(key), value) {
+                        (Some(_), Value::Null) => {
+                            object.remove(key);
+                        }
+                        (Some(node), _) => merge_patch(node, value),
+                        (None, Value::Null) => {}
+                        (None, _) => {
+                            let mut node = Jsona::from(Value::Null);
+                            merge_patch(&mut node, value);
+                            object.insert(key, node);
+                        }
+                    }
+                }
+            }
+        }
+        _ => replace(doc, Jsona::from(patch)),
+    }
+}
+
+fn apply_operation(doc: &mut Jsona, op: &Value) -> PatchResult<()> {
+    let member = |name: &str| op.get(name).ok_or(format!("missing '{}'", name));
+    let op_name = member("op")?.as_str().ok_or("'op' must be a string")?;
+    let path = parse_pointer(member("path")?)?;
+    if let Some(name) = op.get("annotation") {
+        let name = name.as_str().ok_or("'annotation' must be a string")?;
+        let node = lookup_mut(doc, &path)?;
+        return apply_annotation_operation(node, op_name, name, op);
+    }
+    match op_name {
+        "add" => add(doc, &path, Jsona::from(member("value")?)),
+        "remove" => remove(doc, &path).map(|_| ()),
+        "replace" => {
+            let node = lookup_mut(doc, &path)?;
+            replace(node, Jsona::from(member("value")?));
+            Ok(())
+        }
+        "move" => {
+            let from = parse_pointer(member("from")?)?;
+            if from.len() < path.len() && path.starts_with(&from) {
+                return Err("cannot move a node into one of its children".into());
+            }
+            let node = remove(doc, &from)?;
+            add(doc, &path, node)
+        }
+        "copy" => {
+            let from = parse_pointer(member("from")?)?;
+            let node = lookup_mut(doc, &from)?.clone();
+            add(doc, &path, node)
+        }
+        "test" => {
+            let node = lookup_mut(doc, &path)?;
+            if &Value::from(&*node) == member("value")? {
+                Ok(())
+            } else {
+                Err(format!("test failed at '{}'", pointer::join(&path)))
+            }
+        }
+        _ => Err(format!("unknown op '{}'", op_name)),
+    }
+}
+
+fn apply_annotation_operation(
+    node: &mut Jsona,
+    op_name: &str,
+    name: &str,
+    op: &Value,
+) -> PatchResult<()> {
+    let value = || op.get("value").ok_or("missing 'value'");
+    let annotations = node.get_annotations_mut();
+    let idx = annotations.iter().position(|a| a.name == name);
+    let missing = || format!("annotation '{}' not found", name);
+    match op_name {
+        "add" => match idx {
+            Some(idx) => annotations[idx].value = Jsona::from(value()?),
+            None => annotations.push(Annotation::new(name, value()?)),
+        },
+        "remove" => {
+            annotations.remove(idx.ok_or_else(missing)?);
+        }
+        "replace" => annotations[idx.ok_or_else(missing)?].value = Jsona::from(value()?),
+        "test" => {
+            if &Value::from(&annotations[idx.ok_or_else(missing)?].value) != value()? {
+                return Err(format!("test failed at annotation '{}'", name));
+            }
+        }
+        _ => return Err(format!("op '{}' is not supported on annotations", op_name)),
+    }
+    Ok(())
+}
+
+fn parse_pointer(value: &Value) -> PatchResult<Vec<String>> {
+    let text = value.as_str().ok_or("pointer must be a string")?;
+    pointer::split(text).ok_or_else(|| format!("invalid pointer '{}'", text))
+}
+
+fn lookup_mut<'a>(doc: &'a mut Jsona, path: &[String]) -> PatchResult<&'a mut Jsona> {
+    path.iter()
+        .try_fold(doc, |v, k| v.key_mut(k))
+        .ok_or_else(|| format!("path '{}' does not exist", pointer::join(path)))
+}
+
+/// Replace the value of `node`, keeping its annotations and adding those of `value` with
+/// other names.
+fn replace(node: &mut Jsona, mut value: Jsona) {
+    let mut annotations = std::mem::take(node.get_annotations_mut());
+    let incoming: Vec<_> = std::mem::take(value.get_annotations_mut())
+        .into_iter()
+        .filter(|a| annotations.iter().all(|v| v.name != a.name))
+        .collect();
+    annotations.extend(incoming);
+    *node = value.with_annotations(annotations);
+}
+
+fn add(doc: &mut Jsona, path: &[String], node: Jsona) -> PatchResult<()> {
+    let (key, parent_path) = match path.split_last() {
+        Some(v) => v,
+        None => {
+            replace(doc, node);
+            return Ok(());
+        }
+    };
+    match lookup_mut(doc, parent_path)? {
+        Jsona::Object(object) => {
+            match object.get_mut(key) {
+                Some(existing) => replace(existing, node),
+                None => {
+                    object.insert(key, node);
+                }
+            }
+            Ok(())
+        }
+        Jsona::Array(Array { elements, .. }) => {
+            let idx = if key == "-" {
+                elements.len()
+            } else {
+                parse_index(key, elements.len() + 1)?
+            };
+            elements.insert(idx, node);
+            Ok(())
+        }
+        _ => Err(format!(
+            "path '{}' is not a container",
+            pointer::join(parent_path)
+        )),
+    }
+}
+
+fn remove(doc: &mut Jsona, path: &[String]) -> PatchResult<Jsona> {
+    let (key, parent_path) = path.split_last().ok_or("cannot remove the root")?;
+    match lookup_mut(doc, parent_path)? {
+        Jsona::Object(object) => object
+            .remove(key)
+            .map(|p| p.value)
+            .ok_or_else(|| format!("path '{}' does not exist", pointer::join(path))),
+        Jsona::Array(Array { elements, .. }) => {
+            let idx = parse_index(key, elements.len())?;
+            Ok(elements.remove(idx))
+        }
+        _ => Err(format!("path '{}' does not exist", pointer::join(path))),
+    }
+}
+
+fn parse_index(key: &str, len: usize) -> PatchResult<usize> {
+    match key.parse::<usize>() {
+        Ok(idx) if idx < len && (key == "0" || !key.starts_with('0')) => Ok(idx),
+        _ => Err(format!("invalid array index '{}'", key)),
+    }
+}
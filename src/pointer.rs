@@ -0,0 +1,30 @@
+//! JSON pointer (RFC 6901) helpers.
+
+/// Escape a single reference token.
+pub fn escape(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+/// Unescape a single reference token.
+pub fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+/// Build a pointer from unescaped reference tokens.
+pub fn join<S: AsRef<str>>(tokens: &[S]) -> String {
+    tokens
+        .iter()
+        .map(|t| format!("/{}", escape(t.as_ref())))
+        .collect()
+}
+
+/// Split a pointer into unescaped reference tokens, `None` if it is not a valid pointer.
+pub fn split(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return None;
+    }
+    Some(pointer[1..].split('/').map(unescape).collect())
+}
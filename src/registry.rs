@@ -0,0 +1,374 @@
+//! Declared annotation signatures, and validation of documents against them.
+//!
+//! ```
+//! use jsona::registry::{AnnotationRegistry, AnnotationSignature, ArgumentSchema, Target};
+//!
+//! let mut registry = AnnotationRegistry::builtin();
+//! registry.register(AnnotationSignature::new("optional", ArgumentSchema::None));
+//! registry.register(
+//!     AnnotationSignature::new("min", ArgumentSchema::Number).with_targets(&[Target::Scalar]),
+//! );
+//! let doc = jsona::from_str("{ a: 1, @optinal }").unwrap();
+//! let errors = registry.validate(&doc);
+//! assert_eq!(errors[0].info, "unknown annotation '@optinal', did you mean '@optional'?");
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::error::{Error, ErrorKind};
+use crate::include::INCLUDE;
+use crate::resolve::{SAVE, USE};
+use crate::syntax::{Annotation, Array, Jsona, Object};
+
+/// The kind of node an annotation is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Object,
+    Array,
+    /// Null, boolean, number or string.
+    Scalar,
+}
+
+impl Target {
+    pub fn of(node: &Jsona) -> Self {
+        match node {
+            Jsona::Object(..) => Target::Object,
+            Jsona::Array(..) => Target::Array,
+            _ => Target::Scalar,
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Target::Object => write!(f, "object"),
+            Target::Array => write!(f, "array"),
+            Target::Scalar => write!(f, "scalar"),
+        }
+    }
+}
+
+/// The shape of an annotation argument.
+///
+/// An omitted argument reads as null, so `@foo` and `@foo(null)` are the same.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentSchema {
+    /// No argument.
+    None,
+    Any,
+    Boolean,
+    Integer,
+    /// An integer or a float.
+    Number,
+    String,
+    /// An array whose elements match the schema.
+    Array(Box<ArgumentSchema>),
+    /// Any object.
+    Object,
+    /// A `[min, max]` array of two values matching the schema, numbers need `min <= max`.
+    Bounds(Box<ArgumentSchema>),
+    /// One of the strings.
+    Keyword(Vec<String>),
+    OneOf(Vec<ArgumentSchema>),
+    /// Either no argument or one matching the schema.
+    Optional(Box<ArgumentSchema>),
+}
+
+impl ArgumentSchema {
+    fn plural(&self) -> String {
+        match self {
+            ArgumentSchema::None => "nulls".into(),
+            ArgumentSchema::Any => "values".into(),
+            ArgumentSchema::Boolean => "booleans".into(),
+            ArgumentSchema::Integer => "integers".into(),
+            ArgumentSchema::Number => "numbers".into(),
+            ArgumentSchema::String => "strings".into(),
+            ArgumentSchema::Array(schema) => format!("arrays of {}", schema.plural()),
+            ArgumentSchema::Object => "objects".into(),
+            ArgumentSchema::Bounds(schema) => format!("[min, max] pairs of {}", schema.plural()),
+            ArgumentSchema::Keyword(..) => "strings".into(),
+            ArgumentSchema::OneOf(schemas) => {
+                let names: Vec<_> = schemas.iter().map(|v| v.plural()).collect();
+                names.join(" or ")
+            }
+            ArgumentSchema::Optional(schema) => schema.plural(),
+        }
+    }
+    /// Find a part of `value` that does not match, with the expected shape.
+    fn mismatch<'a>(&self, value: &'a Jsona) -> Option<(&'a Jsona, &ArgumentSchema)> {
+        let ok = match self {
+            ArgumentSchema::None => value.is_null(),
+            ArgumentSchema::Any => true,
+            ArgumentSchema::Boolean => value.is_boolean(),
+            ArgumentSchema::Integer => value.is_integer(),
+            ArgumentSchema::Number => value.is_integer() || value.is_float(),
+            ArgumentSchema::String => value.is_string(),
+            ArgumentSchema::Array(schema) => match value {
+                Jsona::Array(Array { elements, .. }) => {
+                    return elements.iter().find_map(|v| schema.mismatch(v));
+                }
+                _ => false,
+            },
+            ArgumentSchema::Object => value.is_object(),
+            ArgumentSchema::Bounds(schema) => match value {
+                Jsona::Array(Array { elements, .. }) if elements.len() == 2 => {
+                    if let Some(mismatch) = elements.iter().find_map(|v| schema.mismatch(v)) {
+                        return Some(mismatch);
+                    }
+                    match (number(&elements[0]), number(&elements[1])) {
+                        (Some(min), Some(max)) => min <= max,
+                        _ => true,
+                    }
+                }
+                _ => false,
+            },
+            ArgumentSchema::Keyword(words) => match value {
+                Jsona::String(v) => words.contains(&v.value),
+                _ => false,
+            },
+            ArgumentSchema::OneOf(schemas) => schemas.iter().any(|v| v.mismatch(value).is_none()),
+            ArgumentSchema::Optional(schema) => {
+                return if value.is_null() {
+                    None
+                } else {
+                    schema.mismatch(value)
+                };
+            }
+        };
+        if ok {
+            None
+        } else {
+            Some((value, self))
+        }
+    }
+}
+
+impl fmt::Display for ArgumentSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgumentSchema::None => write!(f, "no argument"),
+            ArgumentSchema::Any => write!(f, "any value"),
+            ArgumentSchema::Boolean => write!(f, "a boolean"),
+            ArgumentSchema::Integer => write!(f, "an integer"),
+            ArgumentSchema::Number => write!(f, "a number"),
+            ArgumentSchema::String => write!(f, "a string"),
+            ArgumentSchema::Array(schema) => write!(f, "an array of {}", schema.plural()),
+            ArgumentSchema::Object => write!(f, "an object"),
+            ArgumentSchema::Bounds(schema) => {
+                write!(f, "a [min, max] pair of {}", schema.plural())
+            }
+            ArgumentSchema::Keyword(words) => {
+                let words: Vec<_> = words.iter().map(|v| format!("'{}'", v)).collect();
+                write!(f, "one of {}", words.join(", "))
+            }
+            ArgumentSchema::OneOf(schemas) => {
+                let names: Vec<_> = schemas.iter().map(|v| v.to_string()).collect();
+                write!(f, "{}", names.join(" or "))
+            }
+            ArgumentSchema::Optional(schema) => write!(f, "{}", schema),
+        }
+    }
+}
+
+/// What an annotation may be attached to and what argument it takes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnnotationSignature {
+    pub name: String,
+    pub targets: Vec<Target>,
+    pub argument: ArgumentSchema,
+    /// The annotation may appear more than once on a node.
+    pub repeatable: bool,
+}
+
+impl AnnotationSignature {
+    /// A signature allowed on any node, at most once.
+    pub fn new(name: &str, argument: ArgumentSchema) -> Self {
+        Self {
+            name: name.to_string(),
+            targets: vec![Target::Object, Target::Array, Target::Scalar],
+            argument,
+            repeatable: false,
+        }
+    }
+    pub fn with_targets(mut self, targets: &[Target]) -> Self {
+        self.targets = targets.to_vec();
+        self
+    }
+    pub fn repeatable(mut self) -> Self {
+        self.repeatable = true;
+        self
+    }
+}
+
+/// The annotations known to a tool.
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationRegistry {
+    signatures: BTreeMap<String, AnnotationSignature>,
+}
+
+impl AnnotationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// A registry of the annotations this crate interprets: `@save`, `@use` and `@include`.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        for name in [SAVE, USE, INCLUDE] {
+            registry.register(AnnotationSignature::new(name, ArgumentSchema::String));
+        }
+        registry
+    }
+    /// Add a signature, replacing any with the same name.
+    pub fn register(&mut self, signature: AnnotationSignature) -> &mut Self {
+        self.signatures.insert(signature.name.clone(), signature);
+        self
+    }
+    pub fn get(&self, name: &str) -> Option<&AnnotationSignature> {
+        self.signatures.get(name)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &AnnotationSignature> {
+        self.signatures.values()
+    }
+    /// Check the annotations of every node of `doc`.
+    ///
+    /// Reports unknown annotations, arguments of the wrong shape, annotations on a kind of node
+    /// they are not allowed on, and repeated annotations, in document order.
+    pub fn validate(&self, doc: &Jsona) -> Vec<Error> {
+        let mut errors = Vec::new();
+        self.validate_node(doc, &mut errors);
+        errors
+    }
+    fn validate_node(&self, node: &Jsona, errors: &mut Vec<Error>) {
+        let annotations = node.get_annotations();
+        for (i, anno) in annotations.iter().enumerate() {
+            let signature = match self.get(&anno.name) {
+                Some(v) => v,
+                None => {
+                    errors.push(self.unknown(anno));
+                    continue;
+                }
+            };
+            if !signature.repeatable {
+                if let Some(first) = annotations[..i].iter().find(|v| v.name == anno.name) {
+                    errors.push(
+                        Error::new(
+                            format!("duplicate annotation '@{}'", anno.name),
+                            anno.position,
+                        )
+                        .with_kind(ErrorKind::InvalidAnnotation)
+                        .with_label(first.position, "first used here"),
+                    );
+                    continue;
+                }
+            }
+            let target = Target::of(node);
+            if !signature.targets.contains(&target) {
+                let targets: Vec<_> = signature.targets.iter().map(|v| v.to_string()).collect();
+                errors.push(
+                    Error::new(
+                        format!(
+                            "annotation '@{}' is not allowed on {} {}, only on {}",
+                            anno.name,
+                            if target == Target::Scalar { "a" } else { "an" },
+                            target,
+                            targets.join(", ")
+                        ),
+                        anno.position,
+                    )
+                    .with_kind(ErrorKind::InvalidAnnotation),
+                );
+            }
+            if let Some((value, expected)) = signature.argument.mismatch(&anno.value) {
+                let (info, position) = if anno.value.is_null() {
+                    (
+                        format!("annotation '@{}' expects {}", anno.name, expected),
+                        anno.position,
+                    )
+                } else if signature.argument == ArgumentSchema::None {
+                    (
+                        format!("annotation '@{}' takes no argument", anno.name),
+                        *value.get_position(),
+                    )
+                } else {
+                    (
+                        format!(
+                            "invalid argument of '@{}', expected {}, got {}",
+                            anno.name,
+                            expected,
+                            kind_name(value)
+                        ),
+                        *value.get_position(),
+                    )
+                };
+                errors.push(Error::new(info, position).with_kind(ErrorKind::InvalidAnnotation));
+            }
+        }
+        match node {
+            Jsona::Array(Array { elements, .. }) => {
+                elements.iter().for_each(|v| self.validate_node(v, errors))
+            }
+            Jsona::Object(Object { properties, .. }) => properties
+                .iter()
+                .for_each(|p| self.validate_node(&p.value, errors)),
+            _ => {}
+        }
+    }
+    fn unknown(&self, anno: &Annotation) -> Error {
+        let suggestion = self
+            .signatures
+            .keys()
+            .map(|name| (edit_distance(name, &anno.name), name))
+            .filter(|(distance, name)| *distance <= name.chars().count().max(3) / 3)
+            .min();
+        let info = match suggestion {
+            Some((_, name)) => format!(
+                "unknown annotation '@{}', did you mean '@{}'?",
+                anno.name, name
+            ),
+            None => format!("unknown annotation '@{}'", anno.name),
+        };
+        Error::new(info, anno.position).with_kind(ErrorKind::UnknownAnnotation)
+    }
+}
+
+fn number(node: &Jsona) -> Option<f64> {
+    match node {
+        Jsona::Integer(v) => Some(v.value as f64),
+        Jsona::Float(v) => Some(v.value),
+        _ => None,
+    }
+}
+
+fn kind_name(node: &Jsona) -> &'static str {
+    match node {
+        Jsona::Null(..) => "null",
+        Jsona::Boolean(..) => "a boolean",
+        Jsona::Integer(..) => "an integer",
+        Jsona::Float(..) => "a float",
+        Jsona::String(..) => "a string",
+        Jsona::Array(..) => "an array",
+        Jsona::Object(..) => "an object",
+    }
+}
+
+/// Levenshtein distance, in chars.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
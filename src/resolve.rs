@@ -0,0 +1,210 @@
+//! Resolution of `@save("name")` / `@use("name")` references.
+//!
+//! `@save("name")` names the node it is attached to. A node annotated with `@use("name")` is
+//! replaced by a copy of the saved node, without its `@save` annotation but with the annotations
+//! of the use site.
+
+use std::collections::HashMap;
+
+use crate::error::{Error, ErrorKind};
+use crate::pointer;
+use crate::syntax::{Annotation, Array, Jsona, Object, Position};
+
+pub const SAVE: &str = "save";
+pub const USE: &str = "use";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    /// JSON pointer of the saved node.
+    pub path: String,
+    /// Position of the `@save` annotation.
+    pub position: Position,
+}
+
+/// Collect all `@save` names, reporting duplicates and invalid arguments.
+pub fn collect_symbols(doc: &Jsona) -> (HashMap<String, Symbol>, Vec<Error>) {
+    let mut symbols = HashMap::new();
+    let mut errors = Vec::new();
+    collect(doc, &mut Vec::new(), &mut symbols, &mut errors);
+    (symbols, errors)
+}
+
+/// Find the `@use` sites of `name`, as positions of their `@use` annotations.
+pub fn find_uses(doc: &Jsona, name: &str) -> Vec<Position> {
+    let mut positions = Vec::new();
+    walk(doc, &mut |node| {
+        for anno in node.annotations_named(USE) {
+            if reference_name(anno).ok() == Some(name) {
+                positions.push(anno.position);
+            }
+        }
+    });
+    positions
+}
+
+/// Substitute every `@use` site by its saved node.
+///
+/// Reports undefined names, duplicate saves and reference cycles. The document is left
+/// untouched if there is any error.
+pub fn resolve(doc: &mut Jsona) -> Result<(), Vec<Error>> {
+    let (symbols, errors) = collect_symbols(doc);
+    let mut resolver = Resolver {
+        doc,
+        symbols,
+        resolved: HashMap::new(),
+        stack: Vec::new(),
+        errors,
+    };
+    let mut target = doc.clone();
+    resolver.resolve_node(&mut target);
+    if resolver.errors.is_empty() {
+        *doc = target;
+        Ok(())
+    } else {
+        Err(resolver.errors)
+    }
+}
+
+fn reference_name(anno: &Annotation) -> Result<&str, Error> {
+    match anno.value.as_string() {
+        Some(v) => Ok(&v.value),
+        None => Err(Error::new(
+            format!("annotation '@{}' expects a name", anno.name),
+            anno.position,
+        )
+        .with_kind(ErrorKind::InvalidAnnotation)),
+    }
+}
+
+fn walk<F: FnMut(&Jsona)>(node: &Jsona, f: &mut F) {
+    f(node);
+    match node {
+        Jsona::Array(Array { elements, .. }) => elements.iter().for_each(|v| walk(v, f)),
+        Jsona::Object(Object { properties, .. }) => {
+            properties.iter().for_each(|p| walk(&p.value, f))
+        }
+        _ => {}
+    }
+}
+
+fn collect(
+    node: &Jsona,
+    path: &mut Vec<String>,
+    symbols: &mut HashMap<String, Symbol>,
+    errors: &mut Vec<Error>,
+) {
+    for anno in node.annotations_named(SAVE) {
+        match reference_name(anno) {
+            Ok(name) => match symbols.get(name) {
+                Some(exist) => errors.push(Error::new(
+                    format!(
+                        "duplicate '@save(\"{}\")', first saved at line {} column {}",
+                        name, exist.position.line, exist.position.col
+                    ),
+                    anno.position,
+                )
+                .with_kind(ErrorKind::Reference)),
+                None => {
+                    symbols.insert(
+                        name.to_string(),
+                        Symbol {
+                            name: name.to_string(),
+                            path: pointer::join(path),
+                            position: anno.position,
+                        },
+                    );
+                }
+            },
+            Err(err) => errors.push(err),
+        }
+    }
+    match node {
+        Jsona::Array(Array { elements, .. }) => {
+            for (i, elem) in elements.iter().enumerate() {
+                path.push(i.to_string());
+                collect(elem, path, symbols, errors);
+                path.pop();
+            }
+        }
+        Jsona::Object(Object { properties, .. }) => {
+            for prop in properties {
+                path.push(prop.key.clone());
+                collect(&prop.value, path, symbols, errors);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+struct Resolver<'a> {
+    doc: &'a Jsona,
+    symbols: HashMap<String, Symbol>,
+    resolved: HashMap<String, Jsona>,
+    stack: Vec<String>,
+    errors: Vec<Error>,
+}
+
+impl<'a> Resolver<'a> {
+    fn resolve_node(&mut self, node: &mut Jsona) {
+        if let Some(anno) = node.annotation(USE).cloned() {
+            let name = match reference_name(&anno) {
+                Ok(name) => name,
+                Err(err) => {
+                    self.errors.push(err);
+                    return;
+                }
+            };
+            if let Some(mut value) = self.expand(name, &anno) {
+                let annotations = std::mem::take(node.get_annotations_mut());
+                value.get_annotations_mut().extend(annotations);
+                *node = value;
+            }
+            return;
+        }
+        match node {
+            Jsona::Array(Array { elements, .. }) => {
+                elements.iter_mut().for_each(|v| self.resolve_node(v))
+            }
+            Jsona::Object(Object { properties, .. }) => properties
+                .iter_mut()
+                .for_each(|p| self.resolve_node(&mut p.value)),
+            _ => {}
+        }
+    }
+    /// The resolved saved node `name`, without its `@save` annotations.
+    fn expand(&mut self, name: &str, site: &Annotation) -> Option<Jsona> {
+        if self.stack.iter().any(|v| v == name) {
+            let mut cycle = self.stack.clone();
+            cycle.push(name.to_string());
+            self.errors.push(Error::new(
+                format!("reference cycle {}", cycle.join(" -> ")),
+                site.position,
+            )
+            .with_kind(ErrorKind::Reference));
+            return None;
+        }
+        if let Some(value) = self.resolved.get(name) {
+            return Some(value.clone());
+        }
+        let symbol = match self.symbols.get(name) {
+            Some(symbol) => symbol,
+            None => {
+                self.errors.push(Error::new(
+                    format!("undefined reference '{}'", name),
+                    site.position,
+                )
+                .with_kind(ErrorKind::Reference));
+                return None;
+            }
+        };
+        let mut value = self.doc.pointer(&symbol.path).unwrap().clone();
+        value.get_annotations_mut().retain(|a| a.name != SAVE);
+        self.stack.push(name.to_string());
+        self.resolve_node(&mut value);
+        self.stack.pop();
+        self.resolved.insert(name.to_string(), value.clone());
+        Some(value)
+    }
+}
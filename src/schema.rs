@@ -0,0 +1,342 @@
+//! Validation of data against a JSONA document used as a schema.
+//!
+//! A schema looks like the data it describes, with annotations for the constraints an example
+//! can not express:
+//!
+//! ```text
+//! {
+//!     name: "", @pattern("^[a-z]+$")
+//!     age: 0, @optional @min(0) @max(150)
+//!     email: "", @format("email")
+//!     role: "", @enum(["admin", "user"])
+//!     tags: [""],
+//!     extra: null, @type("any")
+//! }
+//! ```
+//!
+//! - The type of a value is the type of the schema node, or `@type("name")`/`@type([..names])`
+//!   with names `null`, `boolean`, `integer`, `number`, `string`, `array`, `object` and `any`.
+//!   A float in the schema accepts any number.
+//! - Properties of an object are required unless annotated with `@optional`, other properties
+//!   are allowed.
+//! - An array with one element constrains every item, with several elements every item has to
+//!   match one of them, an empty array accepts any items.
+//! - `@min`/`@max` bound numbers, and the length of strings, arrays and objects.
+//! - `@pattern` is a regular expression searched in strings, `@enum` lists the allowed values.
+//! - `@format` checks strings against `date`, `time`, `date-time`, `email`, `hostname`, `ipv4`,
+//!   `ipv6`, `uri` or `uuid`; other formats are not checked.
+//!
+//! Arguments of the wrong shape are ignored here, [`register`] the signatures to check a schema
+//! with an [`AnnotationRegistry`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use regex::Regex;
+use serde_json::Value;
+
+use crate::pointer;
+use crate::registry::{AnnotationRegistry, AnnotationSignature, ArgumentSchema, Target};
+use crate::syntax::{Annotation, Array, Jsona, Object, Position};
+
+pub const TYPE: &str = "type";
+pub const OPTIONAL: &str = "optional";
+pub const MIN: &str = "min";
+pub const MAX: &str = "max";
+pub const PATTERN: &str = "pattern";
+pub const ENUM: &str = "enum";
+pub const FORMAT: &str = "format";
+
+const DATE: &str = r"\d{4}-(0[1-9]|1[0-2])-(0[1-9]|[12]\d|3[01])";
+const TIME: &str =
+    r"([01]\d|2[0-3]):[0-5]\d:([0-5]\d|60)(\.\d+)?([zZ]|[+-]([01]\d|2[0-3]):[0-5]\d)";
+const HOSTNAME: &str =
+    r"[a-zA-Z0-9]([a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?(\.[a-zA-Z0-9]([a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?)*";
+
+const TYPES: [&str; 8] = [
+    "null", "boolean", "integer", "number", "string", "array", "object", "any",
+];
+
+/// A value that does not satisfy the schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    /// JSON pointer of the value in the instance.
+    pub pointer: String,
+    /// Position of the schema node, or of the annotation for annotation constraints.
+    pub position: Position,
+    /// The failed constraint: `type`, `required`, `items` or an annotation name.
+    pub keyword: String,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "'{}' {}, see schema at line {} column {}",
+            self.pointer, self.message, self.position.line, self.position.col
+        )
+    }
+}
+
+/// Add the signatures of the schema annotations to `registry`.
+pub fn register(registry: &mut AnnotationRegistry) {
+    let string = || Box::new(ArgumentSchema::String);
+    registry
+        .register(AnnotationSignature::new(
+            TYPE,
+            ArgumentSchema::OneOf(vec![
+                ArgumentSchema::String,
+                ArgumentSchema::Array(string()),
+            ]),
+        ))
+        .register(AnnotationSignature::new(OPTIONAL, ArgumentSchema::None))
+        .register(AnnotationSignature::new(MIN, ArgumentSchema::Number))
+        .register(AnnotationSignature::new(MAX, ArgumentSchema::Number))
+        .register(
+            AnnotationSignature::new(PATTERN, ArgumentSchema::String)
+                .with_targets(&[Target::Scalar]),
+        )
+        .register(AnnotationSignature::new(
+            ENUM,
+            ArgumentSchema::Array(Box::new(ArgumentSchema::Any)),
+        ))
+        .register(
+            AnnotationSignature::new(FORMAT, ArgumentSchema::String)
+                .with_targets(&[Target::Scalar]),
+        );
+}
+
+/// Check `instance` against `schema`, reporting all violations.
+pub fn validate(schema: &Jsona, instance: &Value) -> Vec<Violation> {
+    let mut validator = Validator {
+        patterns: HashMap::new(),
+        violations: Vec::new(),
+    };
+    validator.validate_node(schema, instance, &mut Vec::new());
+    validator.violations
+}
+
+/// Check a JSONA `instance` against `schema`; annotations of the instance are ignored.
+pub fn validate_jsona(schema: &Jsona, instance: &Jsona) -> Vec<Violation> {
+    validate(schema, &Value::from(instance))
+}
+
+struct Validator {
+    patterns: HashMap<String, Result<Regex, String>>,
+    violations: Vec<Violation>,
+}
+
+impl Validator {
+    fn validate_node(&mut self, schema: &Jsona, value: &Value, path: &mut Vec<String>) {
+        if !self.check_type(schema, value, path) {
+            return;
+        }
+        for anno in schema.get_annotations() {
+            self.check_annotation(anno, value, path);
+        }
+        match (schema, value) {
+            (Jsona::Object(Object { properties, .. }), Value::Object(map)) => {
+                for prop in properties {
+                    match map.get(&prop.key) {
+                        Some(v) => {
+                            path.push(prop.key.clone());
+                            self.validate_node(&prop.value, v, path);
+                            path.pop();
+                        }
+                        None if prop.value.has_annotation(OPTIONAL) => {}
+                        None => {
+                            let message = format!("is missing required property '{}'", prop.key);
+                            self.report(path, prop.position, "required", message);
+                        }
+                    }
+                }
+            }
+            (Jsona::Array(Array { elements, .. }), Value::Array(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    path.push(i.to_string());
+                    match elements.as_slice() {
+                        [] => {}
+                        [elem] => self.validate_node(elem, item, path),
+                        _ => {
+                            if !elements.iter().any(|v| self.matches(v, item, path)) {
+                                let message = format!(
+                                    "matches none of the {} element schemas",
+                                    elements.len()
+                                );
+                                self.report(path, *schema.get_position(), "items", message);
+                            }
+                        }
+                    }
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+    /// Test `value` against `schema` without reporting violations.
+    fn matches(&mut self, schema: &Jsona, value: &Value, path: &mut Vec<String>) -> bool {
+        let len = self.violations.len();
+        self.validate_node(schema, value, path);
+        let matched = self.violations.len() == len;
+        self.violations.truncate(len);
+        matched
+    }
+    /// Check the type of `value`, telling whether the schema node applies further.
+    fn check_type(&mut self, schema: &Jsona, value: &Value, path: &[String]) -> bool {
+        let (types, position) = match schema.annotation(TYPE) {
+            Some(anno) => {
+                let types = match anno.value_as::<Value>() {
+                    Ok(Value::String(v)) => vec![v],
+                    Ok(Value::Array(v)) => v
+                        .into_iter()
+                        .filter_map(|v| v.as_str().map(|v| v.to_string()))
+                        .collect(),
+                    _ => return true,
+                };
+                if let Some(name) = types.iter().find(|v| !TYPES.contains(&v.as_str())) {
+                    let message = format!("has unknown type '{}' in schema", name);
+                    self.report(path, anno.position, TYPE, message);
+                    return false;
+                }
+                (types, anno.position)
+            }
+            None => (
+                vec![schema_type(schema).to_string()],
+                *schema.get_position(),
+            ),
+        };
+        if types.iter().any(|name| is_type(name, value)) {
+            return true;
+        }
+        let message = format!("expected {}, got {}", types.join(" or "), type_name(value));
+        self.report(path, position, TYPE, message);
+        false
+    }
+    fn check_annotation(&mut self, anno: &Annotation, value: &Value, path: &[String]) {
+        let message = match anno.name.as_str() {
+            MIN | MAX => {
+                let bound = match anno.value_as::<f64>() {
+                    Ok(v) => v,
+                    Err(_) => return,
+                };
+                let (actual, what) = match value {
+                    Value::Number(v) => (v.as_f64().unwrap_or_default(), "value"),
+                    Value::String(v) => (v.chars().count() as f64, "length"),
+                    Value::Array(v) => (v.len() as f64, "length"),
+                    Value::Object(v) => (v.len() as f64, "length"),
+                    _ => return,
+                };
+                if anno.name == MIN && actual < bound {
+                    format!("{} {} is less than the minimum {}", what, actual, bound)
+                } else if anno.name == MAX && actual > bound {
+                    format!("{} {} is greater than the maximum {}", what, actual, bound)
+                } else {
+                    return;
+                }
+            }
+            PATTERN => {
+                let (pattern, text) = match (anno.value_as::<String>(), value.as_str()) {
+                    (Ok(pattern), Some(text)) => (pattern, text),
+                    _ => return,
+                };
+                match self.regex(&pattern) {
+                    Ok(regex) if regex.is_match(text) => return,
+                    Ok(_) => format!("does not match pattern '{}'", pattern),
+                    Err(err) => format!("has invalid pattern in schema, {}", err),
+                }
+            }
+            ENUM => {
+                let values = match anno.value_as::<Vec<Value>>() {
+                    Ok(v) => v,
+                    Err(_) => return,
+                };
+                if values.contains(value) {
+                    return;
+                }
+                format!("value {} is not one of {}", value, Value::Array(values))
+            }
+            FORMAT => {
+                let (format, text) = match (anno.value_as::<String>(), value.as_str()) {
+                    (Ok(format), Some(text)) => (format, text),
+                    _ => return,
+                };
+                if self.is_format(&format, text).unwrap_or(true) {
+                    return;
+                }
+                format!("is not a valid {}", format)
+            }
+            _ => return,
+        };
+        self.report(path, anno.position, &anno.name, message);
+    }
+    fn regex(&mut self, pattern: &str) -> &Result<Regex, String> {
+        self.patterns
+            .entry(pattern.to_string())
+            .or_insert_with(|| Regex::new(pattern).map_err(|e| e.to_string()))
+    }
+    /// Check `text` against a known format, `None` for unknown formats.
+    fn is_format(&mut self, format: &str, text: &str) -> Option<bool> {
+        let pattern = match format {
+            "date" => DATE.to_string(),
+            "time" => TIME.to_string(),
+            "date-time" => format!("{}[tT ]{}", DATE, TIME),
+            "email" => format!(r"[^\s@]+@{}", HOSTNAME),
+            "hostname" => HOSTNAME.to_string(),
+            "uri" => r"[a-zA-Z][a-zA-Z0-9+.-]*:\S+".to_string(),
+            "uuid" => r"[0-9a-fA-F]{8}-([0-9a-fA-F]{4}-){3}[0-9a-fA-F]{12}".to_string(),
+            "ipv4" => return Some(text.parse::<Ipv4Addr>().is_ok()),
+            "ipv6" => return Some(text.parse::<Ipv6Addr>().is_ok()),
+            _ => return None,
+        };
+        match self.regex(&format!("^(?:{})$", pattern)) {
+            Ok(regex) => Some(regex.is_match(text)),
+            Err(_) => None,
+        }
+    }
+    fn report(&mut self, path: &[String], position: Position, keyword: &str, message: String) {
+        self.violations.push(Violation {
+            pointer: pointer::join(path),
+            position,
+            keyword: keyword.to_string(),
+            message,
+        });
+    }
+}
+
+pub(crate) fn schema_type(schema: &Jsona) -> &'static str {
+    match schema {
+        Jsona::Null(..) => "null",
+        Jsona::Boolean(..) => "boolean",
+        Jsona::Integer(..) => "integer",
+        Jsona::Float(..) => "number",
+        Jsona::String(..) => "string",
+        Jsona::Array(..) => "array",
+        Jsona::Object(..) => "object",
+    }
+}
+
+pub(crate) fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(..) => "boolean",
+        Value::Number(v) if v.is_f64() => "number",
+        Value::Number(..) => "integer",
+        Value::String(..) => "string",
+        Value::Array(..) => "array",
+        Value::Object(..) => "object",
+    }
+}
+
+pub(crate) fn is_type(name: &str, value: &Value) -> bool {
+    match name {
+        "any" => true,
+        "number" => value.is_number(),
+        "integer" => match value.as_f64() {
+            Some(v) if value.is_f64() => v.fract() == 0.0,
+            _ => value.is_i64() || value.is_u64(),
+        },
+        _ => name == type_name(value),
+    }
+}
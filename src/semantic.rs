@@ -0,0 +1,154 @@
+//! Equality and hashing over values and annotations, ignoring positions.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::syntax::{Annotation, Array, Boolean, Float, Integer, Jsona, Object, Property, String};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SemanticOptions {
+    pub ignore_annotations: bool,
+    pub ignore_key_order: bool,
+}
+
+impl Jsona {
+    /// Compare values and annotations, ignoring positions.
+    ///
+    /// Annotations are compared by name and value; annotations with different names may appear
+    /// in any order.
+    pub fn semantic_eq(&self, other: &Jsona, options: &SemanticOptions) -> bool {
+        if !options.ignore_annotations
+            && !annotations_eq(self.get_annotations(), other.get_annotations(), options)
+        {
+            return false;
+        }
+        match (self, other) {
+            (Jsona::Null(..), Jsona::Null(..)) => true,
+            (
+                Jsona::Boolean(Boolean { value: a, .. }),
+                Jsona::Boolean(Boolean { value: b, .. }),
+            ) => a == b,
+            (
+                Jsona::Integer(Integer { value: a, .. }),
+                Jsona::Integer(Integer { value: b, .. }),
+            ) => a == b,
+            (Jsona::Float(Float { value: a, .. }), Jsona::Float(Float { value: b, .. })) => a == b,
+            (Jsona::String(String { value: a, .. }), Jsona::String(String { value: b, .. })) => {
+                a == b
+            }
+            (Jsona::Array(Array { elements: a, .. }), Jsona::Array(Array { elements: b, .. })) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b.iter())
+                        .all(|(a, b)| a.semantic_eq(b, options))
+            }
+            (
+                Jsona::Object(Object { properties: a, .. }),
+                Jsona::Object(Object { properties: b, .. }),
+            ) => {
+                if a.len() != b.len() {
+                    return false;
+                }
+                if options.ignore_key_order {
+                    // Match as multisets, each property of `b` pairs with one of `a` at most.
+                    let mut rest: Vec<&Property> = b.iter().collect();
+                    a.iter().all(|p| {
+                        let found = rest
+                            .iter()
+                            .position(|q| q.key == p.key && p.value.semantic_eq(&q.value, options));
+                        found.map(|i| rest.swap_remove(i)).is_some()
+                    })
+                } else {
+                    a.iter()
+                        .zip(b.iter())
+                        .all(|(p, q)| p.key == q.key && p.value.semantic_eq(&q.value, options))
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Feed a canonical form of the node to `state`: positions are skipped, properties are
+    /// hashed independent of their order, duplicate keys included, and annotations in name
+    /// order.
+    ///
+    /// Nodes that are `semantic_eq` under the same `ignore_annotations` setting hash equally.
+    pub fn semantic_hash<H: Hasher>(&self, state: &mut H, options: &SemanticOptions) {
+        if !options.ignore_annotations {
+            let annotations = sorted_annotations(self.get_annotations());
+            annotations.len().hash(state);
+            for anno in annotations {
+                anno.name.hash(state);
+                anno.value.semantic_hash(state, options);
+            }
+        }
+        match self {
+            Jsona::Null(..) => 0u8.hash(state),
+            Jsona::Boolean(Boolean { value, .. }) => {
+                1u8.hash(state);
+                value.hash(state);
+            }
+            Jsona::Integer(Integer { value, .. }) => {
+                2u8.hash(state);
+                value.hash(state);
+            }
+            Jsona::Float(Float { value, .. }) => {
+                3u8.hash(state);
+                hash_f64(*value, state);
+            }
+            Jsona::String(String { value, .. }) => {
+                4u8.hash(state);
+                value.hash(state);
+            }
+            Jsona::Array(Array { elements, .. }) => {
+                5u8.hash(state);
+                elements.len().hash(state);
+                for elem in elements {
+                    elem.semantic_hash(state, options);
+                }
+            }
+            Jsona::Object(Object { properties, .. }) => {
+                6u8.hash(state);
+                properties.len().hash(state);
+                let mut hashes: Vec<u64> = properties
+                    .iter()
+                    .map(|prop| {
+                        let mut hasher = DefaultHasher::new();
+                        prop.key.hash(&mut hasher);
+                        prop.value.semantic_hash(&mut hasher, options);
+                        hasher.finish()
+                    })
+                    .collect();
+                hashes.sort_unstable();
+                hashes.hash(state);
+            }
+        }
+    }
+}
+
+/// Hashes the canonical form with annotations, see [`Jsona::semantic_hash`].
+impl Hash for Jsona {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.semantic_hash(state, &SemanticOptions::default())
+    }
+}
+
+fn sorted_annotations(annotations: &[Annotation]) -> Vec<&Annotation> {
+    let mut annotations: Vec<&Annotation> = annotations.iter().collect();
+    annotations.sort_by(|a, b| a.name.cmp(&b.name));
+    annotations
+}
+
+fn annotations_eq(a: &[Annotation], b: &[Annotation], options: &SemanticOptions) -> bool {
+    a.len() == b.len()
+        && sorted_annotations(a)
+            .iter()
+            .zip(sorted_annotations(b).iter())
+            .all(|(a, b)| a.name == b.name && a.value.semantic_eq(&b.value, options))
+}
+
+fn hash_f64<H: Hasher>(value: f64, state: &mut H) {
+    // `0.0 == -0.0`, so they must hash the same.
+    let value = if value == 0.0 { 0.0 } else { value };
+    value.to_bits().hash(state);
+}
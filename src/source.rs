@@ -0,0 +1,117 @@
+//! Source files of composed documents, so positions and errors can name the file they are in.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::syntax::{Jsona, Position};
+
+/// A file in a [`SourceMap`]. `FileId::NONE` is used by positions not tied to any file.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Deserialize, Serialize,
+)]
+pub struct FileId(pub u32);
+
+impl FileId {
+    pub const NONE: FileId = FileId(0);
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::NONE
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    pub path: String,
+    pub source: String,
+}
+
+impl SourceFile {
+    /// The text of `line`, counted from 1, without the line break.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        self.source
+            .split('\n')
+            .nth(line.checked_sub(1)?)
+            .map(|v| v.trim_end_matches('\r'))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Add a file, replacing the source of a file with the same path.
+    pub fn add(&mut self, path: &str, source: &str) -> FileId {
+        match self.find(path) {
+            Some(id) => {
+                self.files[id.0 as usize - 1].source = source.to_string();
+                id
+            }
+            None => {
+                self.files.push(SourceFile {
+                    path: path.to_string(),
+                    source: source.to_string(),
+                });
+                FileId(self.files.len() as u32)
+            }
+        }
+    }
+    pub fn find(&self, path: &str) -> Option<FileId> {
+        self.files
+            .iter()
+            .position(|v| v.path == path)
+            .map(|idx| FileId(idx as u32 + 1))
+    }
+    pub fn get(&self, id: FileId) -> Option<&SourceFile> {
+        self.files.get((id.0 as usize).checked_sub(1)?)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (FileId, &SourceFile)> {
+        self.files
+            .iter()
+            .enumerate()
+            .map(|(idx, v)| (FileId(idx as u32 + 1), v))
+    }
+    /// Parse the file `id`, positions in the result and in errors refer to it.
+    pub fn parse(&self, id: FileId) -> Result<Jsona, Error> {
+        let file = self
+            .get(id)
+            .ok_or_else(|| Error::new(format!("unknown file id {}", id.0), Position::default()))?;
+        match crate::from_str(&file.source) {
+            Ok(mut node) => {
+                node.set_file(id);
+                Ok(node)
+            }
+            Err(mut err) => {
+                err.position.file = id;
+                for label in err.labels.iter_mut() {
+                    label.position.file = id;
+                }
+                Err(err)
+            }
+        }
+    }
+    /// Format `position` as `path:line:col`, or `line:col` if it is not in a known file.
+    pub fn location(&self, position: &Position) -> String {
+        match self.get(position.file) {
+            Some(file) => format!("{}:{}:{}", file.path, position.line, position.col),
+            None => format!("{}:{}", position.line, position.col),
+        }
+    }
+    /// The source line `position` is on.
+    pub fn line(&self, position: &Position) -> Option<&str> {
+        self.get(position.file)?.line(position.line)
+    }
+    /// Render `error` as `path:line:col: info`, followed by the offending source line.
+    pub fn render(&self, error: &Error) -> String {
+        let mut output = format!("{}: {}", self.location(&error.position), error.info);
+        if let Some(line) = self.line(&error.position) {
+            output.push('\n');
+            output.push_str(line);
+        }
+        output
+    }
+}
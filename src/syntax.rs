@@ -1,8 +1,14 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
 use std::string;
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+use crate::error::{Error, ErrorKind};
+use crate::source::FileId;
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 #[serde(tag = "type")]
 pub enum Jsona {
     Null(Null),
@@ -14,73 +20,211 @@ pub enum Jsona {
     Object(Object),
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Null {
     pub annotations: Vec<Annotation>,
     pub position: Position,
+    pub end: Position,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Boolean {
     pub value: bool,
     pub annotations: Vec<Annotation>,
     pub position: Position,
+    pub end: Position,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Integer {
     pub value: i64,
     pub annotations: Vec<Annotation>,
     pub position: Position,
+    pub end: Position,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Float {
     pub value: f64,
     pub annotations: Vec<Annotation>,
     pub position: Position,
+    pub end: Position,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct String {
     pub value: string::String,
     pub annotations: Vec<Annotation>,
     pub position: Position,
+    pub end: Position,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Array {
     pub elements: Vec<Jsona>,
     pub annotations: Vec<Annotation>,
     pub position: Position,
+    pub end: Position,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Object {
     pub properties: Vec<Property>,
     pub annotations: Vec<Annotation>,
     pub position: Position,
+    pub end: Position,
+    #[serde(skip)]
+    pub index: KeyIndex,
 }
 
-#[derive(Debug, PartialEq, Deserialize, Serialize)]
+/// Optional key lookup index of an `Object`, see [`Object::build_index`].
+///
+/// The index is a cache: it never takes part in equality, and lookups fall back to a linear
+/// scan if `properties` changed length since the index was built or an entry went stale.
+#[derive(Debug, Clone, Default)]
+pub struct KeyIndex(Option<(usize, HashMap<string::String, usize>)>);
+
+impl PartialEq for KeyIndex {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Object {
+    /// Build a hash index over the keys for constant time lookups.
+    ///
+    /// Keys may repeat; lookups always resolve to the first property with the key. The
+    /// methods of `Object` keep the index up to date, call this again after editing
+    /// `properties` directly.
+    pub fn build_index(&mut self) {
+        let mut map = HashMap::with_capacity(self.properties.len());
+        for (i, prop) in self.properties.iter().enumerate() {
+            map.entry(prop.key.clone()).or_insert(i);
+        }
+        self.index = KeyIndex(Some((self.properties.len(), map)));
+    }
+    pub fn is_indexed(&self) -> bool {
+        self.index.0.is_some()
+    }
+    /// Index of the first property with `key`.
+    pub fn position_of(&self, key: &str) -> Option<usize> {
+        if let Some((len, map)) = &self.index.0 {
+            if *len == self.properties.len() {
+                let hit = map.get(key).copied();
+                // Direct edits of `properties` may leave a stale entry, only trust it if it
+                // still is the first property with `key`, otherwise fall back to a scan.
+                let first = |&i: &usize| {
+                    self.properties[i].key == key
+                        && self.properties[..i].iter().all(|p| p.key != key)
+                };
+                if let Some(i) = hit.filter(first) {
+                    return Some(i);
+                }
+            }
+        }
+        self.properties.iter().position(|p| p.key == key)
+    }
+    pub fn get(&self, key: &str) -> Option<&Jsona> {
+        self.position_of(key).map(|i| &self.properties[i].value)
+    }
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Jsona> {
+        self.position_of(key)
+            .map(move |i| &mut self.properties[i].value)
+    }
+    /// Replace the value of the first property with `key` and return the old value, or append
+    /// a new property.
+    pub fn insert(&mut self, key: &str, value: Jsona) -> Option<Jsona> {
+        match self.position_of(key) {
+            Some(i) => Some(std::mem::replace(&mut self.properties[i].value, value)),
+            None => {
+                self.properties.push(Property::new(key, value));
+                if let Some((len, map)) = &mut self.index.0 {
+                    if *len + 1 == self.properties.len() {
+                        *len += 1;
+                        map.insert(key.to_string(), *len - 1);
+                    }
+                }
+                None
+            }
+        }
+    }
+    /// Remove the first property with `key`, keeping the order of the others.
+    pub fn remove(&mut self, key: &str) -> Option<Property> {
+        let i = self.position_of(key)?;
+        let prop = self.properties.remove(i);
+        if self.is_indexed() {
+            self.build_index();
+        }
+        Some(prop)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Property {
     pub key: string::String,
     pub position: Position,
     pub value: Jsona,
 }
 
+impl Property {
+    pub fn new(key: &str, value: Jsona) -> Self {
+        Self {
+            key: key.to_string(),
+            position: Position::default(),
+            value,
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Deserialize, Serialize)]
 pub struct Annotation {
     pub name: string::String,
     pub position: Position,
-    pub value: Value,
+    pub end: Position,
+    pub value: Jsona,
+}
+
+impl Annotation {
+    pub fn new<T: Into<Jsona>>(name: &str, value: T) -> Self {
+        Self {
+            name: name.to_string(),
+            position: Position::default(),
+            end: Position::default(),
+            value: value.into(),
+        }
+    }
+    /// Deserialize the value, errors are reported at the position of the offending node.
+    pub fn value_as<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        crate::de::from_jsona(&self.value).map_err(|(message, position)| {
+            Error::new(
+                format!("invalid value of annotation '@{}', {}", self.name, message),
+                position,
+            )
+            .with_kind(ErrorKind::InvalidAnnotation)
+        })
+    }
 }
 
-#[derive(Clone, Copy, PartialEq, Debug, Eq, Deserialize, Serialize)]
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub struct Position {
     pub index: usize,
     pub line: usize,
     pub col: usize,
+    /// The file in a [`SourceMap`](crate::source::SourceMap), if any.
+    #[serde(default, skip_serializing_if = "FileId::is_none")]
+    pub file: FileId,
+}
+impl fmt::Debug for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("Position");
+        s.field("index", &self.index)
+            .field("line", &self.line)
+            .field("col", &self.col);
+        if !self.file.is_none() {
+            s.field("file", &self.file);
+        }
+        s.finish()
+    }
 }
 impl Default for Position {
     fn default() -> Self {
@@ -88,14 +232,33 @@ impl Default for Position {
             index: 0,
             line: 1,
             col: 1,
+            file: FileId::NONE,
         }
     }
 }
 
 impl Position {
     pub fn new(index: usize, line: usize, col: usize) -> Self {
-        Position { index, line, col }
+        Position {
+            index,
+            line,
+            col,
+            file: FileId::NONE,
+        }
     }
+    /// Test if `self` lies within `[start, end)`, comparing by line and column.
+    pub fn is_within(&self, start: &Position, end: &Position) -> bool {
+        (start.line, start.col) <= (self.line, self.col)
+            && (self.line, self.col) < (end.line, end.col)
+    }
+}
+
+/// The innermost thing found by [`Jsona::node_at`].
+#[derive(Debug, PartialEq)]
+pub enum Located<'a> {
+    Node(&'a Jsona),
+    Key(&'a Property),
+    Annotation(&'a Annotation),
 }
 
 macro_rules! define_is (
@@ -138,9 +301,7 @@ impl Jsona {
 
     pub fn key(&self, key: &str) -> Option<&Self> {
         match self {
-            Jsona::Object(Object {
-                properties: value, ..
-            }) => value.iter().find(|p| p.key == key).map(|v| &v.value),
+            Jsona::Object(object) => object.get(key),
             Jsona::Array(Array {
                 elements: value, ..
             }) => {
@@ -153,11 +314,95 @@ impl Jsona {
             _ => None,
         }
     }
+    pub fn key_mut(&mut self, key: &str) -> Option<&mut Self> {
+        match self {
+            Jsona::Object(object) => object.get_mut(key),
+            Jsona::Array(Array {
+                elements: value, ..
+            }) => {
+                if let Ok(idx) = key.parse::<usize>() {
+                    value.get_mut(idx)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
     pub fn retrive(&self, path: &[&str]) -> Option<&Self> {
-        path.iter()
-            .fold(Some(self), |v, &b| v.and_then(|v| v.key(b)))
+        path.iter().try_fold(self, |v, &b| v.key(b))
+    }
+    /// Look up a node by JSON pointer, e.g. `/foo/0/bar`.
+    pub fn pointer(&self, pointer: &str) -> Option<&Self> {
+        crate::pointer::split(pointer)?
+            .iter()
+            .try_fold(self, |v, b| v.key(b))
+    }
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Self> {
+        crate::pointer::split(pointer)?
+            .iter()
+            .try_fold(self, |v, b| v.key_mut(b))
     }
 
+    /// Find the innermost node, property key or annotation at `position`, together with the
+    /// path of the node it belongs to. Only `line` and `col` of `position` are used.
+    pub fn node_at(&self, position: &Position) -> Option<(Vec<string::String>, Located<'_>)> {
+        let mut path = Vec::new();
+        self.locate(position, &mut path).map(|v| (path, v))
+    }
+    fn locate(&self, position: &Position, path: &mut Vec<string::String>) -> Option<Located<'_>> {
+        if let Some(anno) = self
+            .get_annotations()
+            .iter()
+            .find(|a| position.is_within(&a.position, &a.end))
+        {
+            return Some(Located::Annotation(anno));
+        }
+        if !position.is_within(self.get_position(), self.get_end_position()) {
+            return None;
+        }
+        match self {
+            Jsona::Array(Array { elements, .. }) => {
+                for (i, elem) in elements.iter().enumerate() {
+                    path.push(i.to_string());
+                    if let Some(v) = elem.locate(position, path) {
+                        return Some(v);
+                    }
+                    path.pop();
+                }
+            }
+            Jsona::Object(Object { properties, .. }) => {
+                for prop in properties.iter() {
+                    path.push(prop.key.clone());
+                    if position.is_within(&prop.position, prop.value.get_position()) {
+                        return Some(Located::Key(prop));
+                    }
+                    if let Some(v) = prop.value.locate(position, path) {
+                        return Some(v);
+                    }
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+        Some(Located::Node(self))
+    }
+    /// Build key indexes for all objects in the tree, see [`Object::build_index`].
+    pub fn build_index(&mut self) {
+        match self {
+            Jsona::Array(Array { elements, .. }) => {
+                elements.iter_mut().for_each(|v| v.build_index());
+            }
+            Jsona::Object(object) => {
+                object.build_index();
+                object
+                    .properties
+                    .iter_mut()
+                    .for_each(|p| p.value.build_index());
+            }
+            _ => {}
+        }
+    }
     pub fn get_position(&self) -> &Position {
         match self {
             Jsona::Null(Null { position, .. }) => position,
@@ -169,6 +414,66 @@ impl Jsona {
             Jsona::Object(Object { position, .. }) => position,
         }
     }
+    pub fn get_end_position(&self) -> &Position {
+        match self {
+            Jsona::Null(Null { end, .. }) => end,
+            Jsona::Boolean(Boolean { end, .. }) => end,
+            Jsona::Integer(Integer { end, .. }) => end,
+            Jsona::Float(Float { end, .. }) => end,
+            Jsona::String(String { end, .. }) => end,
+            Jsona::Array(Array { end, .. }) => end,
+            Jsona::Object(Object { end, .. }) => end,
+        }
+    }
+    pub fn set_end_position(&mut self, position: Position) {
+        match self {
+            Jsona::Null(Null { end, .. }) => *end = position,
+            Jsona::Boolean(Boolean { end, .. }) => *end = position,
+            Jsona::Integer(Integer { end, .. }) => *end = position,
+            Jsona::Float(Float { end, .. }) => *end = position,
+            Jsona::String(String { end, .. }) => *end = position,
+            Jsona::Array(Array { end, .. }) => *end = position,
+            Jsona::Object(Object { end, .. }) => *end = position,
+        }
+    }
+    pub(crate) fn positions_mut(&mut self) -> (&mut Position, &mut Position) {
+        match self {
+            Jsona::Null(Null { position, end, .. }) => (position, end),
+            Jsona::Boolean(Boolean { position, end, .. }) => (position, end),
+            Jsona::Integer(Integer { position, end, .. }) => (position, end),
+            Jsona::Float(Float { position, end, .. }) => (position, end),
+            Jsona::String(String { position, end, .. }) => (position, end),
+            Jsona::Array(Array { position, end, .. }) => (position, end),
+            Jsona::Object(Object { position, end, .. }) => (position, end),
+        }
+    }
+    /// Call `f` on all positions in the node, its annotations and children.
+    pub fn for_each_position_mut<F: FnMut(&mut Position)>(&mut self, f: &mut F) {
+        let (position, end) = self.positions_mut();
+        f(position);
+        f(end);
+        for anno in self.get_annotations_mut() {
+            f(&mut anno.position);
+            f(&mut anno.end);
+            anno.value.for_each_position_mut(f);
+        }
+        match self {
+            Jsona::Array(Array { elements, .. }) => elements
+                .iter_mut()
+                .for_each(|v| v.for_each_position_mut(f)),
+            Jsona::Object(Object { properties, .. }) => {
+                for prop in properties.iter_mut() {
+                    f(&mut prop.position);
+                    prop.value.for_each_position_mut(f);
+                }
+            }
+            _ => {}
+        }
+    }
+    /// Mark all positions in the node, its annotations and children as coming from `file`.
+    pub fn set_file(&mut self, file: FileId) {
+        self.for_each_position_mut(&mut |position| position.file = file);
+    }
     pub fn get_annotations(&self) -> &Vec<Annotation> {
         match self {
             Jsona::Null(Null { annotations, .. }) => annotations,
@@ -180,6 +485,30 @@ impl Jsona {
             Jsona::Object(Object { annotations, .. }) => annotations,
         }
     }
+    pub fn with_annotation(mut self, annotation: Annotation) -> Self {
+        self.get_annotations_mut().push(annotation);
+        self
+    }
+    pub fn with_annotations<I: IntoIterator<Item = Annotation>>(mut self, annotations: I) -> Self {
+        self.get_annotations_mut().extend(annotations);
+        self
+    }
+    /// The first annotation named `name`.
+    pub fn annotation(&self, name: &str) -> Option<&Annotation> {
+        self.get_annotations().iter().find(|a| a.name == name)
+    }
+    pub fn has_annotation(&self, name: &str) -> bool {
+        self.annotation(name).is_some()
+    }
+    pub fn annotations_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Annotation> {
+        self.get_annotations()
+            .iter()
+            .filter(move |a| a.name == name)
+    }
+    /// Deserialize the value of the first annotation named `name`, `Ok(None)` if there is none.
+    pub fn annotation_as<T: DeserializeOwned>(&self, name: &str) -> Result<Option<T>, Error> {
+        self.annotation(name).map(|a| a.value_as()).transpose()
+    }
     pub fn get_annotations_mut(&mut self) -> &mut Vec<Annotation> {
         match self {
             Jsona::Null(Null { annotations, .. }) => annotations,
@@ -203,12 +532,12 @@ impl From<&Jsona> for Value {
             Jsona::String(String { value, .. }) => value.to_owned().into(),
             Jsona::Array(Array {
                 elements: value, ..
-            }) => Value::Array(value.into_iter().map(|v| v.into()).collect()),
+            }) => Value::Array(value.iter().map(|v| v.into()).collect()),
             Jsona::Object(Object {
                 properties: value, ..
             }) => Value::Object(
                 value
-                    .into_iter()
+                    .iter()
                     .map(|v| (v.key.to_owned(), Value::from(&v.value)))
                     .collect::<Map<string::String, Value>>(),
             ),
@@ -238,3 +567,72 @@ impl From<Jsona> for Value {
         }
     }
 }
+
+/// Integers beyond the range of `i64` become floats and may lose precision, use
+/// [`to_jsona`](crate::to_jsona) to reject them.
+impl From<Value> for Jsona {
+    fn from(value: Value) -> Self {
+        let annotations = Vec::new();
+        let position = Position::default();
+        let end = Position::default();
+        match value {
+            Value::Null => Jsona::Null(Null {
+                annotations,
+                position,
+                end,
+            }),
+            Value::Bool(value) => Jsona::Boolean(Boolean {
+                value,
+                annotations,
+                position,
+                end,
+            }),
+            Value::Number(value) => match value.as_i64() {
+                Some(value) => Jsona::Integer(Integer {
+                    value,
+                    annotations,
+                    position,
+                    end,
+                }),
+                None => Jsona::Float(Float {
+                    value: value.as_f64().unwrap_or_default(),
+                    annotations,
+                    position,
+                    end,
+                }),
+            },
+            Value::String(value) => Jsona::String(String {
+                value,
+                annotations,
+                position,
+                end,
+            }),
+            Value::Array(value) => Jsona::Array(Array {
+                elements: value.into_iter().map(|v| v.into()).collect(),
+                annotations,
+                position,
+                end,
+            }),
+            Value::Object(value) => Jsona::Object(Object {
+                properties: value
+                    .into_iter()
+                    .map(|(key, value)| Property {
+                        key,
+                        position,
+                        value: value.into(),
+                    })
+                    .collect(),
+                annotations,
+                position,
+                end,
+                index: KeyIndex::default(),
+            }),
+        }
+    }
+}
+
+impl From<&Value> for Jsona {
+    fn from(value: &Value) -> Self {
+        value.clone().into()
+    }
+}
@@ -0,0 +1,386 @@
+//! Generation of TypeScript declarations from a JSONA example or schema document.
+//!
+//! The root becomes an interface, or a type alias if it is not an object, and so does every
+//! nested object. Types follow the nodes, or `@type` and `@enum` as in
+//! [`schema`](crate::schema); `@optional` properties are optional. Interfaces of nested objects
+//! are named after their key, or by `@save("Name")`, and `@use("Name")` refers to them; a
+//! `@use` without a matching `@save` is `unknown`.
+//! `@description` and, with [`to_typescript_source`], comments before a property or after it
+//! on the same line become JSDoc.
+//!
+//! ```
+//! use jsona::typescript::{to_typescript, TypeScriptOptions};
+//!
+//! let doc = jsona::from_str(r#"{ id: 0, tags: [""], @optional }"#).unwrap();
+//! assert_eq!(
+//!     to_typescript(&doc, &TypeScriptOptions::default()),
+//!     "export interface Root {\n    id: number;\n    tags?: string[];\n}\n"
+//! );
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::highlight::{classify, TokenClass};
+use crate::json_schema::DESCRIPTION;
+use crate::resolve::{SAVE, USE};
+use crate::schema::{schema_type, ENUM, OPTIONAL, TYPE};
+use crate::syntax::{Array, Jsona, Object};
+
+#[derive(Debug, Clone)]
+pub struct TypeScriptOptions {
+    /// Name of the root type, unless the root has `@save`. A number is appended if a nested
+    /// `@save` takes the name.
+    pub root: String,
+    /// Spaces per nesting level.
+    pub indent: usize,
+}
+
+impl Default for TypeScriptOptions {
+    fn default() -> Self {
+        Self {
+            root: "Root".into(),
+            indent: 4,
+        }
+    }
+}
+
+/// Generate declarations for `doc`.
+pub fn to_typescript(doc: &Jsona, options: &TypeScriptOptions) -> String {
+    generate(doc, options, Comments::default())
+}
+
+/// Parse `source` and generate declarations, with its comments as documentation.
+pub fn to_typescript_source(source: &str, options: &TypeScriptOptions) -> Result<String, Error> {
+    let doc = crate::from_str(source)?;
+    Ok(generate(&doc, options, Comments::collect(source)))
+}
+
+/// Comments documenting the root and the properties, by char index of their key.
+#[derive(Debug, Default)]
+struct Comments {
+    root: Vec<String>,
+    keys: HashMap<usize, Vec<String>>,
+}
+
+impl Comments {
+    /// Comments on their own lines document the following key, or the root if they come first;
+    /// comments after a key on the same line document that key.
+    fn collect(source: &str) -> Self {
+        let chars: Vec<char> = source.chars().collect();
+        let mut comments = Comments::default();
+        let mut pending = Vec::new();
+        // Line of the last token, and the key on that line if any.
+        let mut last: Option<(usize, Option<usize>)> = None;
+        for span in classify(source) {
+            if span.class == TokenClass::Comment {
+                let text: String = chars[span.start.index..span.end.index].iter().collect();
+                match last {
+                    Some((line, key)) if line == span.start.line => {
+                        if let Some(key) = key {
+                            comments.keys.entry(key).or_default().push(text);
+                        }
+                    }
+                    _ => pending.push(text),
+                }
+                continue;
+            }
+            let line_key = match last {
+                Some((line, key)) if line == span.start.line => key,
+                _ => None,
+            };
+            if span.class == TokenClass::Key {
+                last = Some((span.start.line, Some(span.start.index)));
+                comments
+                    .keys
+                    .entry(span.start.index)
+                    .or_default()
+                    .append(&mut pending);
+            } else {
+                if last.is_none() {
+                    comments.root.append(&mut pending);
+                }
+                last = Some((span.start.line, line_key));
+                pending.clear();
+            }
+        }
+        comments
+    }
+}
+
+fn generate(doc: &Jsona, options: &TypeScriptOptions, comments: Comments) -> String {
+    let mut saved = HashSet::new();
+    collect_saved(doc, &mut saved);
+    let mut generator = Generator {
+        options,
+        comments,
+        names: saved.clone(),
+        saved,
+        declared: HashSet::new(),
+        declarations: Vec::new(),
+    };
+    let root = match doc.annotation_as::<String>(SAVE) {
+        Ok(Some(name)) => name,
+        _ if generator.names.contains(&options.root) => {
+            generator.unique_name(&options.root, &options.root)
+        }
+        _ => options.root.clone(),
+    };
+    let doc_lines = generator.comments.root.clone();
+    generator.declare(doc, &root, doc_lines);
+    let declarations: Vec<String> = generator.declarations.into_iter().flatten().collect();
+    declarations.join("\n")
+}
+
+fn collect_saved(node: &Jsona, names: &mut HashSet<String>) {
+    if let Ok(Some(name)) = node.annotation_as::<String>(SAVE) {
+        names.insert(name);
+    }
+    match node {
+        Jsona::Array(Array { elements, .. }) => {
+            elements.iter().for_each(|v| collect_saved(v, names))
+        }
+        Jsona::Object(Object { properties, .. }) => properties
+            .iter()
+            .for_each(|p| collect_saved(&p.value, names)),
+        _ => {}
+    }
+}
+
+struct Generator<'a> {
+    options: &'a TypeScriptOptions,
+    comments: Comments,
+    /// Names taken by `@save` and generated interfaces.
+    names: HashSet<String>,
+    /// Names given by `@save`.
+    saved: HashSet<String>,
+    declared: HashSet<String>,
+    /// Declarations in order, reserved before their members are generated.
+    declarations: Vec<Option<String>>,
+}
+
+impl<'a> Generator<'a> {
+    /// Declare `node` as `name`, an interface for objects, else a type alias.
+    fn declare(&mut self, node: &Jsona, name: &str, mut doc: Vec<String>) {
+        self.names.insert(name.to_string());
+        self.declared.insert(name.to_string());
+        let slot = self.declarations.len();
+        self.declarations.push(None);
+        doc.extend(description(node));
+        let mut output = jsdoc(&doc, "");
+        match node {
+            Jsona::Object(Object { properties, .. }) if !node.has_annotation(TYPE) => {
+                output.push_str(&format!("export interface {} {{\n", name));
+                let indent = " ".repeat(self.options.indent);
+                for prop in properties {
+                    let hint = format!("{}{}", name, pascal_case(&prop.key));
+                    let ty = self.type_of(&prop.value, &pascal_case(&prop.key), &hint);
+                    let mut doc = self
+                        .comments
+                        .keys
+                        .get(&prop.position.index)
+                        .cloned()
+                        .unwrap_or_default();
+                    doc.extend(description(&prop.value));
+                    output.push_str(&jsdoc(&doc, &indent));
+                    let optional = if prop.value.has_annotation(OPTIONAL) {
+                        "?"
+                    } else {
+                        ""
+                    };
+                    output.push_str(&format!(
+                        "{}{}{}: {};\n",
+                        indent,
+                        format_key(&prop.key),
+                        optional,
+                        ty
+                    ));
+                }
+                output.push_str("}\n");
+            }
+            _ => {
+                let ty = self.inline_type(node, &format!("{}Item", name));
+                output.push_str(&format!("export type {} = {};\n", name, ty));
+            }
+        }
+        self.declarations[slot] = Some(output);
+    }
+    /// The type of a property or element, declaring named types on the way.
+    fn type_of(&mut self, node: &Jsona, hint: &str, fallback: &str) -> String {
+        if let Ok(Some(name)) = node.annotation_as::<String>(USE) {
+            return if self.saved.contains(&name) {
+                name
+            } else {
+                "unknown".to_string()
+            };
+        }
+        if let Ok(Some(name)) = node.annotation_as::<String>(SAVE) {
+            if !self.declared.contains(&name) {
+                self.declare(node, &name, Vec::new());
+            }
+            return name;
+        }
+        if node.is_object() && !node.has_annotation(TYPE) {
+            let name = self.unique_name(hint, fallback);
+            self.declare(node, &name, Vec::new());
+            return name;
+        }
+        self.inline_type(node, &format!("{}Item", hint))
+    }
+    /// The type of `node` without declaring it, `hint` names objects among array elements.
+    fn inline_type(&mut self, node: &Jsona, hint: &str) -> String {
+        if let Ok(Some(values)) = node.annotation_as::<Vec<Value>>(ENUM) {
+            if !values.is_empty() {
+                let literals: Vec<_> = values.iter().map(|v| v.to_string()).collect();
+                return union(literals);
+            }
+        }
+        let types = match node.annotation_as::<Value>(TYPE) {
+            Ok(Some(Value::String(name))) => vec![name],
+            Ok(Some(Value::Array(names))) => names
+                .iter()
+                .filter_map(|v| v.as_str().map(|v| v.to_string()))
+                .collect(),
+            _ => vec![schema_type(node).to_string()],
+        };
+        let mut parts = Vec::new();
+        for name in types {
+            let part = match name.as_str() {
+                "array" => match node {
+                    Jsona::Array(Array { elements, .. }) => {
+                        let mut elements_types = Vec::new();
+                        for elem in elements {
+                            elements_types.push(self.type_of(elem, hint, hint));
+                        }
+                        match union(elements_types) {
+                            ty if ty.is_empty() => "unknown[]".to_string(),
+                            ty if ty.contains(' ') => format!("({})[]", ty),
+                            ty => format!("{}[]", ty),
+                        }
+                    }
+                    _ => "unknown[]".to_string(),
+                },
+                "object" => "Record<string, unknown>".to_string(),
+                "integer" | "number" => "number".to_string(),
+                "null" | "boolean" | "string" => name,
+                _ => "unknown".to_string(),
+            };
+            parts.push(part);
+        }
+        union(parts)
+    }
+    /// `name`, or `fallback` if it is taken, made unique with a number.
+    fn unique_name(&mut self, name: &str, fallback: &str) -> String {
+        let mut candidates = vec![name.to_string(), fallback.to_string()];
+        candidates.retain(|v| v.starts_with(|c: char| c.is_ascii_alphabetic()));
+        let base = candidates
+            .first()
+            .cloned()
+            .unwrap_or_else(|| format!("{}Type", self.options.root));
+        if let Some(name) = candidates.into_iter().find(|v| !self.names.contains(v)) {
+            self.names.insert(name.clone());
+            return name;
+        }
+        let name = (2..)
+            .map(|i| format!("{}{}", base, i))
+            .find(|v| !self.names.contains(v))
+            .unwrap();
+        self.names.insert(name.clone());
+        name
+    }
+}
+
+fn description(node: &Jsona) -> Option<String> {
+    node.annotation_as::<String>(DESCRIPTION).ok().flatten()
+}
+
+/// Join distinct types with `|`.
+fn union(types: Vec<String>) -> String {
+    let mut output: Vec<String> = Vec::new();
+    for ty in types {
+        if !output.contains(&ty) {
+            output.push(ty);
+        }
+    }
+    output.join(" | ")
+}
+
+/// A JSDoc block from comments and descriptions, empty if there is no text.
+fn jsdoc(doc: &[String], indent: &str) -> String {
+    let lines: Vec<String> = doc
+        .iter()
+        .flat_map(|text| comment_lines(text))
+        .map(|line| line.replace("*/", "*\\/"))
+        .collect();
+    match lines.as_slice() {
+        [] => String::new(),
+        [line] => format!("{}/** {} */\n", indent, line),
+        _ => {
+            let mut output = format!("{}/**\n", indent);
+            for line in &lines {
+                if line.is_empty() {
+                    output.push_str(&format!("{} *\n", indent));
+                } else {
+                    output.push_str(&format!("{} * {}\n", indent, line));
+                }
+            }
+            output.push_str(&format!("{} */\n", indent));
+            output
+        }
+    }
+}
+
+/// The text lines of a comment or description, without comment markers and outer blank lines.
+fn comment_lines(text: &str) -> Vec<String> {
+    let text = if let Some(text) = text.strip_prefix("//") {
+        text
+    } else if let Some(text) = text.strip_prefix("/*") {
+        text.strip_suffix("*/").unwrap_or(text)
+    } else {
+        text
+    };
+    let mut lines: Vec<String> = text
+        .lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix('*').unwrap_or(line);
+            line.trim().to_string()
+        })
+        .collect();
+    while lines.first().map(|v| v.is_empty()).unwrap_or(false) {
+        lines.remove(0);
+    }
+    while lines.last().map(|v| v.is_empty()).unwrap_or(false) {
+        lines.pop();
+    }
+    lines
+}
+
+fn pascal_case(key: &str) -> String {
+    key.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|v| !v.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            std::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+fn format_key(key: &str) -> String {
+    let mut chars = key.chars();
+    let is_identifier = match chars.next() {
+        Some(ch) => {
+            (ch.is_ascii_alphabetic() || ch == '_' || ch == '$')
+                && chars.all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '$')
+        }
+        None => false,
+    };
+    if is_identifier {
+        key.to_string()
+    } else {
+        serde_json::to_string(key).unwrap()
+    }
+}
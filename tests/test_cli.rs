@@ -0,0 +1,111 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+fn jsona(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_jsona"))
+        .args(args)
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn test_check() {
+    let output = jsona(&["check"], "{ a: 1 }");
+    assert!(output.status.success());
+
+    let output = jsona(&["check", "-"], "{\n  a: 1,\n  b: {}, @use(\"x\")\n}");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("error[E0102]: undefined reference 'x'\n --> <stdin>:3:11"));
+
+    let output = jsona(&["check"], "[1 2]");
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).starts_with("error[E0001]: expected token ','"));
+
+    let output = jsona(&["check", "missing.jsona"], "");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_fmt() {
+    let output = jsona(&["fmt"], "{a:1,b:[true]}");
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "{\n    a: 1,\n    b: [\n        true,\n    ],\n}\n"
+    );
+
+    let output = jsona(&["fmt", "--check"], "{a:1}");
+    assert_eq!(output.status.code(), Some(1));
+
+    let output = jsona(&["fmt"], "{a:1} // comment");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "{\n    a: 1,\n} // comment\n");
+
+    let example = include_str!("spec/jsona_example.jsona");
+    let output = jsona(&["fmt"], example);
+    assert!(output.status.success());
+    let formatted = stdout(&output);
+    assert!(formatted.contains("// single line comment"));
+    assert!(jsona(&["fmt", "--check"], &formatted).status.success());
+
+    let dir = std::env::temp_dir().join(format!("jsona-test-fmt-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("a.jsona");
+    let path_str = path.to_str().unwrap();
+    fs::write(&path, "{ a: 'x' }").unwrap();
+    let output = jsona(&["fmt", "--check", path_str], "");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stdout(&output).trim(), path_str);
+    assert!(jsona(&["fmt", path_str], "").status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), "{\n    a: \"x\",\n}\n");
+    assert!(jsona(&["fmt", "--check", path_str], "").status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_convert() {
+    let output = jsona(&["to-json"], "{ a: 1, @min(0)\n b: [0x10] }");
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "{\n  \"a\": 1,\n  \"b\": [\n    16\n  ]\n}\n"
+    );
+
+    let output = jsona(&["from-json"], r#"{"a": [1.5, null], "b c": {}}"#);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "{\n    a: [\n        1.5,\n        null,\n    ],\n    \"b c\": {},\n}\n"
+    );
+}
+
+#[test]
+fn test_get() {
+    let output = jsona(&["get", "/a/1/b"], "{ a: [1, { b: 'x' }] }");
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "\"x\"\n");
+
+    let output = jsona(&["get", "/c"], "{ a: 1 }");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(stderr(&output), "no node at '/c'\n");
+}
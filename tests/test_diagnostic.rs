@@ -0,0 +1,38 @@
+use jsona::diagnostic::{ColorChoice, Renderer};
+use jsona::source::SourceMap;
+
+#[test]
+fn test_render() {
+    let mut sources = SourceMap::new();
+    let id = sources.add("main.jsona", "{\n  x: 1,\n  y: [\n    true,\n");
+    let err = sources.parse(id).unwrap_err();
+    assert_eq!(
+        Renderer::new(ColorChoice::Never).render(&sources, &err),
+        r#"error[E0001]: unexpected token 'eof'
+ --> main.jsona:5:1
+  |
+3 |   y: [
+  |      - array opened here
+...
+5 |
+  | ^
+"#
+    );
+
+    let source = "[\n  \"foo\" \"bar\",\n]";
+    let err = jsona::from_str(source).unwrap_err();
+    let output = Renderer::default().render_source("input", source, &err);
+    assert_eq!(
+        output,
+        r#"error[E0001]: expected token ',', got 'bar' in array
+ --> input:2:9
+  |
+2 |   "foo" "bar",
+  |         ^^^^^
+"#
+    );
+
+    let output = Renderer::new(ColorChoice::Always).render_source("input", source, &err);
+    assert!(output.starts_with("\x1b[1;31merror[E0001]\x1b[0m"));
+    assert!(output.contains("\x1b[1;31m^^^^^\x1b[0m"));
+}
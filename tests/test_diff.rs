@@ -0,0 +1,59 @@
+use jsona::diff::{diff, ChangeKind, DiffOptions};
+use serde_json::json;
+
+#[test]
+fn test_diff() {
+    let old = jsona::from_str(
+        r#"{ @save("User")
+    name: "foo", @upper
+    age: 3,
+    score: 1.0,
+    tags: ["a", "b"],
+    "a/b": 1,
+}"#,
+    )
+    .unwrap();
+    let new = jsona::from_str(
+        r#"{ @save("Person")
+    age: 4, @min(0)
+    name: "foo",
+    score: 1.0000001,
+    tags: ["a"],
+    "a/b": 1,
+    email: "x",
+}"#,
+    )
+    .unwrap();
+    let changes = diff(&old, &new, &DiffOptions::default());
+    let summary: Vec<_> = changes
+        .iter()
+        .map(|c| (c.kind, c.path.as_str(), c.annotation.as_deref()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (ChangeKind::Changed, "", Some("save")),
+            (ChangeKind::Removed, "/name", Some("upper")),
+            (ChangeKind::Added, "/age", Some("min")),
+            (ChangeKind::Changed, "/age", None),
+            (ChangeKind::Changed, "/score", None),
+            (ChangeKind::Removed, "/tags/1", None),
+            (ChangeKind::Added, "/email", None),
+            (ChangeKind::Reordered, "", None),
+        ]
+    );
+    assert_eq!(changes[3].old, Some(json!(3)));
+    assert_eq!(changes[3].new, Some(json!(4)));
+    assert_eq!(changes[3].old_position.unwrap().line, 3);
+    assert_eq!(changes[3].new_position.unwrap().line, 2);
+
+    let options = DiffOptions {
+        ignore_key_order: true,
+        float_epsilon: 1e-6,
+    };
+    let changes = diff(&old, &new, &options);
+    assert!(changes.iter().all(|c| c.path != "/score"));
+    assert!(changes.iter().all(|c| c.kind != ChangeKind::Reordered));
+    assert!(diff(&old, &old, &DiffOptions::default()).is_empty());
+    assert_eq!(old.pointer("/a~1b").unwrap().as_integer().unwrap().value, 1);
+}
@@ -0,0 +1,107 @@
+use jsona::emitter::{emit, emit_with_comments, EmitterOptions};
+use jsona::semantic::SemanticOptions;
+
+const INPUT: &str = include_str!("spec/jsona_example.jsona");
+
+#[test]
+fn test_emit() {
+    let doc = jsona::from_str(
+        r#"{ @foo
+    a: 1, @min(0) @max(10)
+    "b c": [ @array
+        1.0,
+        "x\ny",
+        {},
+    ],
+    d: { @object({k: "v"}) },
+    e: [], @array
+}"#,
+    )
+    .unwrap();
+    let output = emit(&doc, &EmitterOptions { indent: 2 });
+    assert_eq!(
+        output,
+        r#"{ @foo
+  a: 1, @min(0) @max(10)
+  "b c": [ @array
+    1.0,
+    "x\ny",
+    {},
+  ],
+  d: { @object({ k: "v" })
+  },
+  e: [ @array
+  ],
+}
+"#
+    );
+}
+
+#[test]
+fn test_emit_roundtrip() {
+    let doc = jsona::from_str(INPUT).unwrap();
+    let output = emit(&doc, &EmitterOptions::default());
+    let reparsed = jsona::from_str(&output).unwrap();
+    assert!(doc.semantic_eq(&reparsed, &SemanticOptions::default()));
+    assert_eq!(emit(&reparsed, &EmitterOptions::default()), output);
+}
+
+#[test]
+fn test_emit_astral_string() {
+    let doc = jsona::from_str("['你 😀']").unwrap();
+    let output = emit(&doc, &EmitterOptions::default());
+    assert_eq!(output, "[\n    \"你 😀\",\n]\n");
+}
+
+#[test]
+fn test_emit_with_comments() {
+    let source = r#"// leading
+{ // header
+    a: 1, /* after a */ @min(0)
+    // before b
+    b: [ // open
+        1 // one
+        /* own */ /* line */
+    ],
+    c: // key
+    /* value */ 2,
+    d: {
+        // empty
+    },
+} // trailing
+"#;
+    let doc = jsona::from_str(source).unwrap();
+    let output = emit_with_comments(&doc, source, &EmitterOptions { indent: 2 });
+    assert_eq!(
+        output,
+        r#"// leading
+{ // header
+  a: 1, @min(0) /* after a */
+  // before b
+  b: [ // open
+    1, // one
+    /* own */ /* line */
+  ],
+  c: 2, // key
+  /* value */
+  d: {
+    // empty
+  },
+} // trailing
+"#
+    );
+    let reparsed = jsona::from_str(&output).unwrap();
+    let options = EmitterOptions { indent: 2 };
+    assert_eq!(emit_with_comments(&reparsed, &output, &options), output);
+}
+
+#[test]
+fn test_emit_with_comments_roundtrip() {
+    let doc = jsona::from_str(INPUT).unwrap();
+    let output = emit_with_comments(&doc, INPUT, &EmitterOptions::default());
+    assert!(output.contains("/* abc */"));
+    let reparsed = jsona::from_str(&output).unwrap();
+    assert!(doc.semantic_eq(&reparsed, &SemanticOptions::default()));
+    let options = EmitterOptions::default();
+    assert_eq!(emit_with_comments(&reparsed, &output, &options), output);
+}
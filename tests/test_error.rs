@@ -0,0 +1,81 @@
+use jsona::error::ErrorKind;
+use jsona::loader::Loader;
+use jsona::parser::{Parser, MAX_DEPTH};
+
+fn kind_of(input: &str) -> ErrorKind {
+    jsona::from_str(input).unwrap_err().kind
+}
+
+#[test]
+fn test_error_kind() {
+    assert_eq!(
+        kind_of("[1 2]"),
+        ErrorKind::UnexpectedToken {
+            expected: vec![",".into()]
+        }
+    );
+    assert_eq!(
+        kind_of("{ a: 1"),
+        ErrorKind::UnexpectedToken {
+            expected: vec![",".into(), "}".into()]
+        }
+    );
+    assert_eq!(
+        kind_of("{ a: 1,"),
+        ErrorKind::UnexpectedToken {
+            expected: vec!["}".into(), "key".into()]
+        }
+    );
+    assert_eq!(
+        kind_of("[1, "),
+        ErrorKind::UnexpectedToken {
+            expected: vec!["]".into(), "value".into()]
+        }
+    );
+    assert_eq!(kind_of("[1, #]"), ErrorKind::UnexpectedCharacter);
+    assert_eq!(kind_of("\"abc"), ErrorKind::UnterminatedString);
+    assert_eq!(kind_of("\"a\nb\""), ErrorKind::UnterminatedString);
+    assert_eq!(kind_of("1 /* x"), ErrorKind::UnterminatedComment);
+    assert_eq!(kind_of("0xffffffffffffffffff"), ErrorKind::InvalidNumber);
+    assert_eq!(kind_of(r#""\x4""#), ErrorKind::InvalidEscape);
+    assert_eq!(
+        kind_of("{ @1 }"),
+        ErrorKind::UnexpectedToken {
+            expected: vec!["identifier".into()]
+        }
+    );
+
+    let err = jsona::from_str("\"abc").unwrap_err();
+    assert_eq!(err.kind.code(), "E0003");
+    assert_eq!(err.info, "unterminated string literal");
+    let err: Box<dyn std::error::Error> = Box::new(err);
+    assert_eq!(err.to_string(), "unterminated string literal");
+}
+
+#[test]
+fn test_max_depth() {
+    let input = "[".repeat(200) + &"]".repeat(200);
+    assert!(jsona::from_str(&input).is_ok());
+    let err = jsona::from_str_with_max_depth(&input, MAX_DEPTH).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::LimitExceeded);
+    assert_eq!(err.position.col, 129);
+
+    let input = "[[[1]]]";
+    let mut parser = Parser::new(input.chars()).with_max_depth(2);
+    let mut events = Vec::new();
+    struct Collector<'a>(&'a mut Vec<jsona::parser::Event>);
+    impl<'a> jsona::parser::EventReceiver for Collector<'a> {
+        fn on_event(&mut self, event: jsona::parser::Event, _: jsona::syntax::Position) {
+            self.0.push(event)
+        }
+    }
+    let err = parser.parse(&mut Collector(&mut events)).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::LimitExceeded);
+    assert!(Loader::load_from_str(input).is_ok());
+    assert!(Loader::load_with_max_depth(input, 3).is_ok());
+    let err = jsona::from_str_with_max_depth(input, 2).unwrap_err();
+    assert_eq!(err.kind, ErrorKind::LimitExceeded);
+    assert_eq!(err.position.col, 3);
+    let input = "[".repeat(200) + &"]".repeat(200);
+    assert!(jsona::from_str_with_max_depth(&input, 200).is_ok());
+}
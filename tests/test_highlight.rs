@@ -0,0 +1,84 @@
+use jsona::highlight::{classify, to_ansi, to_html, TokenClass};
+
+fn classes(input: &str) -> Vec<(TokenClass, String)> {
+    let chars: Vec<char> = input.chars().collect();
+    classify(input)
+        .into_iter()
+        .map(|span| {
+            let text = chars[span.start.index..span.end.index].iter().collect();
+            (span.class, text)
+        })
+        .collect()
+}
+
+#[test]
+fn test_classify() {
+    use TokenClass::*;
+    let input = r#"/* doc */{ @foo({a: [1]})
+  "k": null, // tail
+  n: -1.5, @bar
+  arr: [true, 'x'],
+}"#;
+    let expect: Vec<(TokenClass, &str)> = vec![
+        (Comment, "/* doc */"),
+        (Punctuation, "{"),
+        (AnnotationName, "@"),
+        (AnnotationName, "foo"),
+        (Punctuation, "("),
+        (AnnotationArgument, "{"),
+        (AnnotationArgument, "a"),
+        (AnnotationArgument, ":"),
+        (AnnotationArgument, "["),
+        (AnnotationArgument, "1"),
+        (AnnotationArgument, "]"),
+        (AnnotationArgument, "}"),
+        (Punctuation, ")"),
+        (Key, "\"k\""),
+        (Punctuation, ":"),
+        (Keyword, "null"),
+        (Punctuation, ","),
+        (Comment, "// tail"),
+        (Key, "n"),
+        (Punctuation, ":"),
+        (Number, "-1.5"),
+        (Punctuation, ","),
+        (AnnotationName, "@"),
+        (AnnotationName, "bar"),
+        (Key, "arr"),
+        (Punctuation, ":"),
+        (Punctuation, "["),
+        (Keyword, "true"),
+        (Punctuation, ","),
+        (String, "'x'"),
+        (Punctuation, "]"),
+        (Punctuation, ","),
+        (Punctuation, "}"),
+    ];
+    let expect: Vec<(TokenClass, std::string::String)> = expect
+        .into_iter()
+        .map(|(c, t)| (c, t.to_string()))
+        .collect();
+    assert_eq!(classes(input), expect);
+}
+
+#[test]
+fn test_render() {
+    let input = "{ a: \"<b>\" } // c";
+    assert_eq!(
+        to_html(input),
+        "<pre class=\"jsona\"><code><span class=\"jsona-punctuation\">{</span> \
+<span class=\"jsona-key\">a</span><span class=\"jsona-punctuation\">:</span> \
+<span class=\"jsona-string\">&quot;&lt;b&gt;&quot;</span> \
+<span class=\"jsona-punctuation\">}</span> <span class=\"jsona-comment\">// c</span></code></pre>"
+    );
+    assert_eq!(to_ansi("[1, x]"), "[\x1b[36m1\x1b[0m, \x1b[35mx\x1b[0m]");
+    // Text that does not lex is kept as is.
+    assert_eq!(to_ansi("[#]"), "[#]");
+}
+
+#[test]
+fn test_comment_positions() {
+    let doc = jsona::from_str("[/* a */1,/* b *//* c */2]").unwrap();
+    assert_eq!(doc.pointer("/0").unwrap().get_position().col, 9);
+    assert_eq!(doc.pointer("/1").unwrap().get_position().col, 25);
+}
@@ -0,0 +1,94 @@
+use jsona::include::{join_path, load, MemoryResolver};
+use serde_json::{json, Value};
+
+#[test]
+fn test_join_path() {
+    assert_eq!(
+        join_path("main.jsona", "common/user.jsona"),
+        "common/user.jsona"
+    );
+    assert_eq!(
+        join_path("api/main.jsona", "../common/./user.jsona"),
+        "common/user.jsona"
+    );
+    assert_eq!(join_path("api/main.jsona", "/user.jsona"), "user.jsona");
+}
+
+#[test]
+fn test_include() {
+    let resolver: MemoryResolver = vec![
+        (
+            "main.jsona",
+            r#"{
+    user: {}, @include("common/user.jsona") @optional
+    users: [
+        {}, @include("./common/user.jsona")
+    ],
+}"#,
+        ),
+        (
+            "common/user.jsona",
+            r#"{ @description("user")
+    name: "foo",
+    group: {}, @include("group.jsona")
+}"#,
+        ),
+        ("common/group.jsona", "{ id: 1 }"),
+    ]
+    .into_iter()
+    .collect();
+    let composed = load("main.jsona", &resolver).unwrap();
+    let user = json!({"name": "foo", "group": {"id": 1}});
+    assert_eq!(
+        Value::from(&composed.root),
+        json!({"user": user, "users": [user]})
+    );
+    let node = composed.root.key("user").unwrap();
+    assert!(node.has_annotation("description"));
+    assert!(node.has_annotation("optional"));
+    assert!(!node.has_annotation("include"));
+    assert_eq!(
+        composed
+            .sources
+            .iter()
+            .map(|(_, v)| v.path.as_str())
+            .collect::<Vec<_>>(),
+        ["main.jsona", "common/user.jsona", "common/group.jsona"]
+    );
+    assert_eq!(composed.file_of(""), Some("main.jsona"));
+    assert_eq!(composed.file_of("/user/name"), Some("common/user.jsona"));
+    assert_eq!(
+        composed.file_of("/users/0/group/id"),
+        Some("common/group.jsona")
+    );
+}
+
+#[test]
+fn test_include_errors() {
+    let resolver: MemoryResolver = vec![
+        ("a.jsona", "{\n  b: {}, @include(\"b.jsona\")\n}"),
+        ("b.jsona", "{\n  a: {}, @include(\"a.jsona\")\n}"),
+        ("c.jsona", "{\n  d: {}, @include(\"d.jsona\")\n}"),
+        (
+            "d.jsona",
+            "{\n  x: 1,\n  y: {}, @include(\"missing.jsona\")\n}",
+        ),
+        ("e.jsona", "[\n  {}, @include(\"f.jsona\")\n]"),
+        ("f.jsona", "{ x: }"),
+    ]
+    .into_iter()
+    .collect();
+    let err = load("a.jsona", &resolver).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "include cycle a.jsona -> b.jsona -> a.jsona at line 2 column 11 in b.jsona\n  included from a.jsona at line 2 column 11"
+    );
+    let err = load("c.jsona", &resolver).unwrap_err();
+    assert_eq!(err.file, "d.jsona");
+    assert_eq!(err.error.position.line, 3);
+    assert_eq!(err.stack.len(), 1);
+    assert_eq!(err.stack[0].0, "c.jsona");
+    let err = load("e.jsona", &resolver).unwrap_err();
+    assert_eq!(err.file, "f.jsona");
+    assert_eq!(err.stack[0].1.line, 2);
+}
@@ -0,0 +1,81 @@
+use jsona::incremental::{reparse, TextEdit};
+use jsona::source::FileId;
+
+const SOURCE: &str = r#"/* head */ { @foo({a: [1]})
+  "k": null, // tail
+  n: -1.5, @bar("é")
+  arr: [true, 'x', { deep: [[0]] }], @baz
+  obj: { @qux
+    x: 1, y: "two",
+  },
+}
+"#;
+
+fn check(source: &str, edit: &TextEdit) {
+    let mut doc = jsona::from_str(source).unwrap();
+    let mut text = source.to_string();
+    let result = reparse(&mut doc, &mut text, edit);
+    assert_eq!(text, edit.apply(source));
+    match jsona::from_str(&text) {
+        Ok(expect) => {
+            assert!(result.is_ok(), "{:?} on {:?}", edit, text);
+            assert_eq!(doc, expect, "{:?} on {:?}", edit, text);
+        }
+        Err(err) => {
+            assert_eq!(
+                format!("{:?}", result.unwrap_err()),
+                format!("{:?}", err),
+                "{:?} on {:?}",
+                edit,
+                text
+            );
+            assert_eq!(doc, jsona::from_str(source).unwrap());
+        }
+    }
+}
+
+#[test]
+fn test_reparse_matches_full_parse() {
+    let len = SOURCE.chars().count();
+    let inserts = [
+        "",
+        "1",
+        ",",
+        " x: [2], ",
+        "]",
+        "}",
+        "\n",
+        "// c",
+        "\"",
+        "@a(1)",
+        "é\n ",
+    ];
+    for start in 0..=len {
+        for width in [0, 1, 3] {
+            for text in inserts.iter() {
+                check(SOURCE, &TextEdit::new(start, start + width, text));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_reparse_keeps_untouched_nodes() {
+    let mut doc = jsona::from_str(SOURCE).unwrap();
+    doc.set_file(FileId(7));
+    let mut text = SOURCE.to_string();
+    let start = SOURCE[..SOURCE.find("y: ").unwrap()].chars().count() + 3;
+    let edit = TextEdit::new(start, start + 5, "[3,\n 4]");
+    reparse(&mut doc, &mut text, &edit).unwrap();
+    let mut expect = jsona::from_str(&text).unwrap();
+    expect.set_file(FileId(7));
+    assert_eq!(doc, expect);
+    assert_eq!(doc.pointer("/obj/y/1").unwrap().get_position().line, 7);
+}
+
+#[test]
+fn test_reparse_line_comment_swallowing_bracket() {
+    let source = "{\n  a: [1, 2]\n}";
+    let start = source.find('2').unwrap() + 1;
+    check(source, &TextEdit::new(start, start, " //"));
+}
@@ -0,0 +1,227 @@
+use jsona::emitter::{emit, EmitterOptions};
+use jsona::json_schema::{from_json_schema, to_json_schema};
+use serde_json::json;
+
+#[test]
+fn test_to_json_schema() {
+    let doc = jsona::from_str(
+        r#"{ @description("A user")
+    name: "", @pattern("^[a-z]+$") @min(1) @max(1.5)
+    age: 0, @optional @min(0) @max(150)
+    score: 0.5, @min(-1.5)
+    email: "", @format("email") @description("Contact")
+    role: "", @enum(["admin", "user"])
+    tags: [""], @max(3)
+    ids: [0, ""],
+    any: [],
+    extra: null, @type("any")
+    id: 0, @type(["integer", "string"])
+    flag: false,
+}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        to_json_schema(&doc),
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "description": "A user",
+            "properties": {
+                "name": { "type": "string", "pattern": "^[a-z]+$", "minLength": 1 },
+                "age": { "type": "integer", "minimum": 0, "maximum": 150 },
+                "score": { "type": "number", "minimum": -1.5 },
+                "email": { "type": "string", "format": "email", "description": "Contact" },
+                "role": { "type": "string", "enum": ["admin", "user"] },
+                "tags": { "type": "array", "items": { "type": "string" }, "maxItems": 3 },
+                "ids": {
+                    "type": "array",
+                    "items": { "anyOf": [{ "type": "integer" }, { "type": "string" }] },
+                },
+                "any": { "type": "array" },
+                "extra": {},
+                "id": { "type": ["integer", "string"] },
+                "flag": { "type": "boolean" },
+            },
+            "required": [
+                "name", "score", "email", "role", "tags", "ids", "any", "extra", "id", "flag",
+            ],
+        })
+    );
+}
+
+#[test]
+fn test_to_json_schema_defs() {
+    let doc = jsona::from_str(
+        r#"{
+    owner: { @save("User")
+        name: "",
+    },
+    members: [{ @use("User") }], @optional
+    admin: {}, @use("User") @description("The admin")
+}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        to_json_schema(&doc),
+        json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "properties": {
+                "owner": { "$ref": "#/$defs/User" },
+                "members": { "type": "array", "items": { "$ref": "#/$defs/User" } },
+                "admin": { "$ref": "#/$defs/User", "description": "The admin" },
+            },
+            "required": ["owner", "admin"],
+            "$defs": {
+                "User": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } },
+                    "required": ["name"],
+                },
+            },
+        })
+    );
+}
+
+#[test]
+fn test_from_json_schema() {
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "description": "A user",
+        "properties": {
+            "name": { "type": "string", "minLength": 1, "default": "bob" },
+            "age": { "type": "integer", "minimum": 18 },
+            "score": { "type": "number", "default": 3 },
+            "email": { "type": "string", "format": "email" },
+            "role": { "enum": ["admin", "user"] },
+            "tags": { "type": "array", "items": { "type": "string" }, "maxItems": 3 },
+            "pair": { "type": "array", "prefixItems": [{ "type": "integer" }, { "type": "boolean" }] },
+            "note": { "type": ["string", "null"] },
+            "extra": {},
+            "owner": { "$ref": "#/$defs/User" },
+            "admin": { "$ref": "#/$defs/User" },
+            "merged": {
+                "allOf": [
+                    { "properties": { "a": { "type": "integer" } }, "required": ["a"] },
+                    { "properties": { "b": { "type": "string" } } },
+                ],
+            },
+        },
+        "required": ["name", "age", "owner"],
+        "$defs": {
+            "User": { "type": "object", "properties": { "id": { "type": "integer" } } },
+        },
+    });
+    let doc = from_json_schema(&schema).unwrap();
+    let options = EmitterOptions { indent: 2 };
+    assert_eq!(
+        emit(&doc, &options),
+        r#"{ @description("A user")
+  admin: { @save("User") @optional
+    id: 0, @optional
+  },
+  age: 18, @min(18)
+  email: "user@example.com", @format("email") @optional
+  extra: null, @type("any") @optional
+  merged: { @optional
+    a: 0,
+    b: "", @optional
+  },
+  name: "bob", @min(1)
+  note: "", @type(["string", "null"]) @optional
+  owner: null, @use("User")
+  pair: [ @optional
+    0,
+    false,
+  ],
+  role: "admin", @enum(["admin", "user"]) @optional
+  score: 3.0, @optional
+  tags: [ @max(3) @optional
+    "",
+  ],
+}
+"#
+    );
+}
+
+#[test]
+fn test_json_schema_round_trip() {
+    let schema = json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "type": "object",
+        "properties": {
+            "id": { "type": "integer", "minimum": 1 },
+            "name": { "type": "string", "pattern": "^[a-z]+$", "description": "Login" },
+            "tags": { "type": "array", "items": { "type": "string" }, "minItems": 1 },
+            "kind": { "type": ["string", "integer"] },
+            "owner": { "$ref": "#/$defs/User" },
+        },
+        "required": ["id", "owner"],
+        "$defs": {
+            "User": { "type": "object", "properties": { "email": { "type": "string", "format": "email" } } },
+        },
+    });
+    let doc = from_json_schema(&schema).unwrap();
+    assert_eq!(to_json_schema(&doc), schema);
+    let err = from_json_schema(&json!({ "$ref": "other.json#/a" })).unwrap_err();
+    assert_eq!(err.info, "unsupported reference 'other.json#/a'");
+}
+
+#[test]
+fn test_from_json_schema_escapes_and_exclusive_bounds() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "a": { "$ref": "#/$defs/My%20Type" },
+            "b": { "$ref": "#/$defs/a~1b%25" },
+            "count": { "type": "integer", "exclusiveMinimum": 0, "exclusiveMaximum": 10.5 },
+            "both": { "type": "integer", "minimum": 3, "exclusiveMinimum": 1, "maximum": 8 },
+            "ratio": { "type": "number", "exclusiveMinimum": 0, "maximum": 1 },
+        },
+        "required": ["a", "b", "count", "both", "ratio"],
+        "$defs": {
+            "My Type": { "type": "string" },
+            "a/b%": { "type": "boolean" },
+        },
+    });
+    let doc = from_json_schema(&schema).unwrap();
+    assert_eq!(
+        emit(&doc, &EmitterOptions { indent: 2 }),
+        r#"{
+  a: "", @save("My Type")
+  b: false, @save("a/b%")
+  both: 3, @min(3) @max(8)
+  count: 1, @min(1) @max(10)
+  ratio: 0.0, @max(1)
+}
+"#
+    );
+    let err = from_json_schema(&json!({ "$ref": "#/$defs/%zz" })).unwrap_err();
+    assert_eq!(err.info, "unsupported reference '#/$defs/%zz'");
+}
+
+#[test]
+fn test_from_json_schema_exclusive_bounds_out_of_range() {
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "a": { "type": "integer", "exclusiveMinimum": 1e19, "exclusiveMaximum": 1e19 },
+            "b": { "type": "integer", "exclusiveMinimum": i64::MAX, "exclusiveMaximum": i64::MIN },
+            "c": { "type": "integer", "exclusiveMaximum": -1e19 },
+            "d": { "type": "integer", "exclusiveMinimum": -1e19, "exclusiveMaximum": 1e18 },
+        },
+        "required": ["a", "b", "c", "d"],
+    });
+    let doc = from_json_schema(&schema).unwrap();
+    assert_eq!(
+        emit(&doc, &EmitterOptions { indent: 2 }),
+        r#"{
+  a: 0,
+  b: 0,
+  c: 0,
+  d: 0, @max(999999999999999999)
+}
+"#
+    );
+}
@@ -4,6 +4,11 @@ use jsona::parser::{Event, EventReceiver, Parser};
 
 const INPUT: &str = include_str!("spec/jsona_example.jsona");
 
+/// Toolchains differ in how `Debug` escapes `'` and NUL in strings.
+fn normalize(text: &str) -> String {
+    text.replace("\\'", "'").replace("\\u{0}", "\\0")
+}
+
 #[test]
 fn test_lex() {
     let expect = include_str!("spec/jsona_example_tok.txt");
@@ -13,7 +18,7 @@ fn test_lex() {
         target.push_str(&format!("{:?}\n", tok))
     }
     // println!("{}", target);
-    assert_eq!(expect, target)
+    assert_eq!(normalize(expect), normalize(&target))
 }
 
 struct EventCollector {
@@ -47,7 +52,7 @@ fn test_parse() {
         target.push_str(&format!("({:?}, {:?})\n", ev, pos))
     }
     // println!("{}", target);
-    assert_eq!(expect, target)
+    assert_eq!(normalize(expect), normalize(&target))
 }
 
 #[test]
@@ -0,0 +1,60 @@
+use jsona::lexer::{Lexer, TokenKind};
+
+fn kinds(input: &str) -> Vec<TokenKind> {
+    Lexer::new(input.chars()).map(|tok| tok.kind).collect()
+}
+
+#[test]
+fn test_astral_string() {
+    assert_eq!(
+        kinds("[\"a😀b\", '𠀀', `\n😀`]"),
+        [
+            TokenKind::LeftBracket,
+            TokenKind::StringLiteral("a😀b".into()),
+            TokenKind::Comma,
+            TokenKind::StringLiteral("𠀀".into()),
+            TokenKind::Comma,
+            TokenKind::StringLiteral("\n😀".into()),
+            TokenKind::RightBracket,
+            TokenKind::Eof,
+        ]
+    );
+    let doc = jsona::from_str("{ s: \"😀\" }").unwrap();
+    assert_eq!(doc.key("s").unwrap().as_string().unwrap().value, "😀");
+}
+
+fn positions(input: &str, comments: bool) -> Vec<(String, usize, usize, usize)> {
+    Lexer::new(input.chars())
+        .with_comments(comments)
+        .map(|tok| {
+            let p = tok.position;
+            (tok.kind.to_string(), p.index, p.line, p.col)
+        })
+        .collect()
+}
+
+#[test]
+fn test_comment_positions() {
+    let input = "[ /* a\n*/ 1, // b\n  2 /**/]";
+    assert_eq!(
+        positions(input, true),
+        [
+            ("[".into(), 0, 1, 1),
+            ("/* a\n*/".into(), 2, 1, 3),
+            ("1".into(), 10, 2, 4),
+            (",".into(), 11, 2, 5),
+            ("// b".into(), 13, 2, 7),
+            ("2".into(), 20, 3, 3),
+            ("/**/".into(), 22, 3, 5),
+            ("]".into(), 26, 3, 9),
+            ("eof".into(), 27, 3, 10),
+        ]
+    );
+    let without: Vec<_> = positions(input, false);
+    let with: Vec<_> = positions(input, true)
+        .into_iter()
+        .filter(|(text, ..)| !text.starts_with('/'))
+        .collect();
+    assert_eq!(without, with);
+    assert_eq!(positions("1 // x", false)[1], ("eof".into(), 6, 1, 7));
+}
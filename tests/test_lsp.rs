@@ -0,0 +1,168 @@
+use serde_json::{json, Value};
+
+const URI: &str = "file:///main.jsona";
+
+fn frame(messages: &[Value]) -> Vec<u8> {
+    let mut input = Vec::new();
+    for message in messages {
+        let body = message.to_string();
+        input.extend(format!("Content-Length: {}\r\n\r\n{}", body.len(), body).bytes());
+    }
+    input
+}
+
+fn unframe(mut output: &str) -> Vec<Value> {
+    let mut messages = Vec::new();
+    while let Some(idx) = output.find("\r\n\r\n") {
+        let length: usize = output["Content-Length: ".len()..idx].parse().unwrap();
+        let body = &output[idx + 4..idx + 4 + length];
+        messages.push(serde_json::from_str(body).unwrap());
+        output = &output[idx + 4 + length..];
+    }
+    messages
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+fn at(line: u64, character: u64) -> Value {
+    json!({ "textDocument": { "uri": URI }, "position": { "line": line, "character": character } })
+}
+
+fn session(text: &str, requests: Vec<Value>) -> Vec<Value> {
+    let mut messages = vec![
+        request(0, "initialize", json!({})),
+        json!({ "jsonrpc": "2.0", "method": "initialized", "params": {} }),
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didOpen",
+            "params": { "textDocument": { "uri": URI, "languageId": "jsona", "version": 1, "text": text } },
+        }),
+    ];
+    messages.extend(requests);
+    messages.push(request(99, "shutdown", Value::Null));
+    messages.push(json!({ "jsonrpc": "2.0", "method": "exit" }));
+    let mut output = Vec::new();
+    jsona::lsp::run(&frame(&messages)[..], &mut output).unwrap();
+    unframe(&String::from_utf8(output).unwrap())
+}
+
+fn result(messages: &[Value], id: u64) -> &Value {
+    &messages.iter().find(|v| v["id"] == id).unwrap()["result"]
+}
+
+const TEXT: &str = r#"{
+  user: { @save("User")
+    name: "😀", @description("name")
+  },
+  owner: {}, @use("User")
+}"#;
+
+#[test]
+fn test_lsp() {
+    let messages = session(
+        TEXT,
+        vec![
+            request(1, "textDocument/documentSymbol", at(0, 0)),
+            request(2, "textDocument/hover", at(2, 18)),
+            request(3, "textDocument/foldingRange", at(0, 0)),
+            request(4, "textDocument/definition", at(4, 15)),
+            request(5, "textDocument/formatting", at(0, 0)),
+            request(6, "textDocument/unknown", at(0, 0)),
+        ],
+    );
+    let capabilities = &result(&messages, 0)["capabilities"];
+    assert_eq!(capabilities["hoverProvider"], true);
+    assert_eq!(messages[1]["params"]["diagnostics"], json!([]));
+
+    let symbols = result(&messages, 1);
+    assert_eq!(symbols[0]["name"], "user");
+    assert_eq!(symbols[0]["kind"], 19);
+    assert_eq!(
+        symbols[0]["children"][0],
+        json!({
+            "name": "name",
+            "kind": 15,
+            "detail": "\"😀\"",
+            "range": { "start": { "line": 2, "character": 4 }, "end": { "line": 2, "character": 14 } },
+            "selectionRange": { "start": { "line": 2, "character": 4 }, "end": { "line": 2, "character": 8 } },
+            "children": [],
+        })
+    );
+
+    let hover = result(&messages, 2);
+    assert_eq!(
+        hover["contents"]["value"],
+        "```jsona\n@description(\"name\")\n```"
+    );
+    assert_eq!(
+        hover["range"]["start"],
+        json!({ "line": 2, "character": 17 })
+    );
+
+    assert_eq!(
+        result(&messages, 3),
+        &json!([
+            { "startLine": 0, "endLine": 4 },
+            { "startLine": 1, "endLine": 2 },
+        ])
+    );
+
+    assert_eq!(
+        result(&messages, 4),
+        &json!({
+            "uri": URI,
+            "range": { "start": { "line": 1, "character": 8 }, "end": { "line": 3, "character": 3 } },
+        })
+    );
+
+    let edits = result(&messages, 5);
+    assert_eq!(
+        edits[0]["range"]["end"],
+        json!({ "line": 5, "character": 1 })
+    );
+    assert!(edits[0]["newText"]
+        .as_str()
+        .unwrap()
+        .starts_with("{\n    user: { @save(\"User\")\n"));
+
+    let unknown = messages.iter().find(|v| v["id"] == 6).unwrap();
+    assert_eq!(unknown["error"]["code"], -32601);
+    assert_eq!(result(&messages, 99), &Value::Null);
+}
+
+#[test]
+fn test_lsp_formatting_comments() {
+    let messages = session(
+        "// config\n{ a: 1, /* one */\n}",
+        vec![request(1, "textDocument/formatting", at(0, 0))],
+    );
+    assert_eq!(
+        result(&messages, 1)[0]["newText"],
+        "// config\n{\n    a: 1, /* one */\n}\n"
+    );
+}
+
+#[test]
+fn test_lsp_diagnostics() {
+    let messages = session("{\n  a: {}, @use(\"x\")\n  b: [1 2]\n}", vec![]);
+    let diagnostics = &messages[1]["params"]["diagnostics"];
+    assert_eq!(diagnostics[0]["code"], "E0001");
+    assert_eq!(
+        diagnostics[0]["range"],
+        json!({ "start": { "line": 2, "character": 8 }, "end": { "line": 2, "character": 9 } })
+    );
+
+    let messages = session("{\n  a: {}, @use(\"x\")\n}", vec![]);
+    let diagnostics = &messages[1]["params"]["diagnostics"];
+    assert_eq!(diagnostics[0]["code"], "E0102");
+    assert_eq!(diagnostics[0]["message"], "undefined reference 'x'");
+
+    let messages = session("[\n  1,\n", vec![]);
+    let diagnostics = &messages[1]["params"]["diagnostics"];
+    assert_eq!(
+        diagnostics[0]["relatedInformation"][0]["message"],
+        "array opened here"
+    );
+}
@@ -0,0 +1,99 @@
+use jsona::matcher::{match_value, Matcher, Verdict};
+use jsona::syntax::{Annotation, Jsona};
+use serde_json::{json, Value};
+
+fn mismatches(expected: &str, actual: Value) -> Vec<String> {
+    let expected = jsona::from_str(expected).unwrap();
+    match_value(&expected, &actual)
+        .into_iter()
+        .map(|v| v.to_string())
+        .collect()
+}
+
+#[test]
+fn test_match_exact() {
+    let expected = r#"{ code: 200, body: { ok: true, items: [1, "a", null] } }"#;
+    let actual = json!({ "code": 200, "body": { "ok": true, "items": [1, "a", null] } });
+    assert_eq!(mismatches(expected, actual), Vec::<String>::new());
+    let actual = json!({ "code": 201, "body": { "items": [1, "b"], "extra": 1 } });
+    assert_eq!(
+        mismatches(expected, actual),
+        vec![
+            "'/code' expected 200, got 201",
+            "'/body/ok' is missing",
+            "'/body/items' expected 3 items, got 2",
+            "'/body/items/1' expected \"a\", got \"b\"",
+            "'/body/extra' is not expected",
+        ]
+    );
+    assert_eq!(
+        mismatches("[1.5, {}]", json!([1, []])),
+        vec![
+            "'/0' expected 1.5, got 1",
+            "'/1' expected type object, got array"
+        ]
+    );
+}
+
+#[test]
+fn test_match_annotations() {
+    let expected = r#"{ @partial
+  id: 0, @type
+  score: 0, @type("number")
+  token: "", @eval("$.length > 10")
+  tags: [ @partial "a" ],
+  users: [ @every { @partial name: "", @type } ],
+  mixed: [ @every 0, @type "", @type ],
+}"#;
+    let actual = json!({
+        "id": 42,
+        "score": 1.5,
+        "token": 7,
+        "tags": ["a", "b"],
+        "users": [{ "name": "x", "age": 1 }, { "name": "y" }],
+        "mixed": [1, "x", 2],
+        "other": null,
+    });
+    assert_eq!(mismatches(expected, actual), Vec::<String>::new());
+    let actual = json!({
+        "id": "42",
+        "score": "1",
+        "token": null,
+        "tags": [],
+        "users": [{ "name": 1 }, {}],
+        "mixed": [true],
+    });
+    assert_eq!(
+        mismatches(expected, actual),
+        vec![
+            "'/id' expected type integer, got string",
+            "'/score' expected type number, got string",
+            "'/tags' expected at least 1 items, got 0",
+            "'/users/0/name' expected type string, got integer",
+            "'/users/1/name' is missing",
+            "'/mixed/0' matches none of the 2 expected items",
+        ]
+    );
+}
+
+#[test]
+fn test_match_custom_handler() {
+    let matcher = Matcher::new().with_handler(
+        "eval",
+        |anno: &Annotation, _: &Jsona, actual: &Value| match (&anno.value, actual) {
+            (Jsona::String(v), Value::Number(n)) if v.value == "positive" => {
+                if n.as_f64().unwrap() > 0.0 {
+                    Verdict::Match
+                } else {
+                    Verdict::Mismatch(format!("{} is not positive", n))
+                }
+            }
+            _ => Verdict::Continue,
+        },
+    );
+    let expected = jsona::from_str(r#"[@every 0, @eval("positive")]"#).unwrap();
+    let found = matcher.match_value(&expected, &json!([1, -2]));
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].to_string(), "'/1' -2 is not positive");
+    assert_eq!((found[0].position.line, found[0].position.col), (1, 9));
+}
@@ -0,0 +1,116 @@
+use jsona::mock::{mock, Mocker, MAX_REPEAT};
+use jsona::registry::AnnotationRegistry;
+use serde_json::json;
+
+const TEMPLATE: &str = r#"{
+    status: "ok",
+    id: 0, @range([10, 20])
+    ratio: 0.0, @range([0.5, 1.5])
+    role: "", @enum(["admin", "user"])
+    email: "", @mock("email")
+    uuid: "", @mock("uuid")
+    created: "", @mock("date-time")
+    flag: false, @mock
+    unknown: "kept", @mock("nope")
+    users: [ @repeat(3)
+        { name: "", @mock("name") },
+    ],
+    tags: [ @repeat([1, 4])
+        "a",
+        "b",
+    ],
+    plain: [1, 2],
+}"#;
+
+#[test]
+fn test_mock_directives() {
+    let doc = jsona::from_str(TEMPLATE).unwrap();
+    for seed in 0..50 {
+        let value = mock(&doc, seed);
+        assert_eq!(value["status"], "ok");
+        let id = value["id"].as_i64().unwrap();
+        assert!((10..=20).contains(&id), "{}", id);
+        let ratio = value["ratio"].as_f64().unwrap();
+        assert!((0.5..=1.5).contains(&ratio), "{}", ratio);
+        assert!(["admin", "user"].contains(&value["role"].as_str().unwrap()));
+        let email = value["email"].as_str().unwrap();
+        assert!(email.contains('@') && email.contains('.'), "{}", email);
+        let uuid = value["uuid"].as_str().unwrap();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(uuid.split('-').nth(2).unwrap().chars().next(), Some('4'));
+        let created = value["created"].as_str().unwrap();
+        assert_eq!(created.len(), 20, "{}", created);
+        assert!(value["flag"].is_boolean());
+        assert_eq!(value["unknown"], "kept");
+        let users = value["users"].as_array().unwrap();
+        assert_eq!(users.len(), 3);
+        assert!(users.iter().all(|v| v["name"].is_string()));
+        let tags = value["tags"].as_array().unwrap();
+        assert!((1..=4).contains(&tags.len()));
+        assert!(tags.iter().enumerate().all(|(i, v)| v == ["a", "b"][i % 2]));
+        assert_eq!(value["plain"], json!([1, 2]));
+    }
+}
+
+#[test]
+fn test_mock_deterministic() {
+    let doc = jsona::from_str(TEMPLATE).unwrap();
+    assert_eq!(mock(&doc, 7), mock(&doc, 7));
+    assert_ne!(mock(&doc, 7), mock(&doc, 8));
+    let mut mocker = Mocker::new(7);
+    let first = mocker.mock(&doc);
+    assert_eq!(first, mock(&doc, 7));
+    assert_ne!(mocker.mock(&doc), first);
+}
+
+#[test]
+fn test_mock_repeat_cap() {
+    let doc = jsona::from_str(
+        "{ a: [ @repeat(1000000000000) 0 ], b: [ @repeat([0, 9223372036854775807]) 0 ] }",
+    )
+    .unwrap();
+    let value = mock(&doc, 1);
+    assert_eq!(value["a"].as_array().unwrap().len() as u64, MAX_REPEAT);
+    assert!(value["b"].as_array().unwrap().len() as u64 <= MAX_REPEAT);
+}
+
+#[test]
+fn test_mock_register() {
+    let mut registry = AnnotationRegistry::new();
+    jsona::mock::register(&mut registry);
+    jsona::schema::register(&mut registry);
+    let doc = jsona::from_str(TEMPLATE).unwrap();
+    let errors = registry.validate(&doc);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(
+        errors[0].info,
+        "invalid argument of '@mock', expected one of 'name', 'word', 'sentence', 'email', \
+         'url', 'uuid', 'ipv4', 'date', 'time', 'date-time', 'boolean', 'integer', 'number', \
+         'string', got a string"
+    );
+    assert_eq!(errors[0].position.line, 10);
+    let doc = jsona::from_str(
+        r#"{ @repeat(2)
+    a: 1, @range(3)
+    b: 1, @range([3, 1])
+    c: [ @repeat([3, 1]) ],
+    d: [ @repeat([1, 2, 3]) ],
+}"#,
+    )
+    .unwrap();
+    let errors: Vec<_> = registry
+        .validate(&doc)
+        .into_iter()
+        .map(|v| v.info)
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            "annotation '@repeat' is not allowed on an object, only on array",
+            "invalid argument of '@range', expected a [min, max] pair of numbers, got an integer",
+            "invalid argument of '@range', expected a [min, max] pair of numbers, got an array",
+            "invalid argument of '@repeat', expected an integer or a [min, max] pair of integers, got an array",
+            "invalid argument of '@repeat', expected an integer or a [min, max] pair of integers, got an array",
+        ]
+    );
+}
@@ -0,0 +1,160 @@
+use jsona::patch::{merge_patch, patch};
+use serde_json::{json, Value};
+
+const BASE: &str = r#"{ @save("Config")
+    host: "localhost", @description("server host")
+    port: 8080, @min(1)
+    tags: ["a", "b"],
+    db: { @optional
+        user: "root",
+    },
+}"#;
+
+#[test]
+fn test_patch() {
+    let mut doc = jsona::from_str(BASE).unwrap();
+    let db_position = *doc.key("db").unwrap().get_position();
+    patch(
+        &mut doc,
+        &json!([
+            {"op": "replace", "path": "/port", "value": 9090},
+            {"op": "add", "path": "/tags/-", "value": "c"},
+            {"op": "remove", "path": "/tags/0"},
+            {"op": "move", "from": "/host", "path": "/hostname"},
+            {"op": "test", "path": "/db/user", "value": "root"},
+            {"op": "add", "path": "/port", "annotation": "max", "value": 65535},
+            {"op": "remove", "path": "", "annotation": "save"},
+        ]),
+    )
+    .unwrap();
+    assert_eq!(
+        Value::from(&doc),
+        json!({"port": 9090, "tags": ["b", "c"], "db": {"user": "root"}, "hostname": "localhost"})
+    );
+    assert!(doc.get_annotations().is_empty());
+    let names = |p: &str| {
+        doc.pointer(p)
+            .unwrap()
+            .get_annotations()
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("/port"), ["min", "max"]);
+    assert_eq!(names("/hostname"), ["description"]);
+    assert_eq!(names("/db"), ["optional"]);
+    assert_eq!(*doc.key("db").unwrap().get_position(), db_position);
+
+    let before = doc.clone();
+    let err = patch(
+        &mut doc,
+        &json!([
+            {"op": "remove", "path": "/port"},
+            {"op": "remove", "path": "/missing"},
+        ]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.info,
+        "patch operation 1 failed, path '/missing' does not exist"
+    );
+    assert_eq!(doc, before);
+}
+
+#[test]
+fn test_patch_add_existing_keeps_annotations() {
+    let mut doc = jsona::from_str(BASE).unwrap();
+    patch(
+        &mut doc,
+        &json!([
+            {"op": "add", "path": "/port", "value": 9090},
+            {"op": "add", "path": "", "value": {"port": 1}},
+        ]),
+    )
+    .unwrap();
+    assert_eq!(Value::from(&doc), json!({"port": 1}));
+    assert_eq!(doc.get_annotations()[0].name, "save");
+
+    let mut doc = jsona::from_str(BASE).unwrap();
+    patch(
+        &mut doc,
+        &json!([{"op": "add", "path": "/port", "value": 9090}]),
+    )
+    .unwrap();
+    let port = doc.key("port").unwrap();
+    assert_eq!(Value::from(port), json!(9090));
+    assert_eq!(port.get_annotations()[0].name, "min");
+}
+
+#[test]
+fn test_patch_move_onto_annotated_target() {
+    let mut doc = jsona::from_str(
+        r#"{
+    a: 1, @min(0) @tag("a")
+    b: 2, @min(1) @max(9)
+    c: 3, @min(2)
+}"#,
+    )
+    .unwrap();
+    patch(
+        &mut doc,
+        &json!([
+            {"op": "move", "from": "/a", "path": "/b"},
+            {"op": "copy", "from": "/b", "path": "/c"},
+        ]),
+    )
+    .unwrap();
+    assert_eq!(Value::from(&doc), json!({"b": 1, "c": 1}));
+    let annotations = |key: &str| {
+        doc.key(key)
+            .unwrap()
+            .get_annotations()
+            .iter()
+            .map(|a| (a.name.clone(), Value::from(&a.value)))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        annotations("b"),
+        [
+            ("min".to_string(), json!(1)),
+            ("max".to_string(), json!(9)),
+            ("tag".to_string(), json!("a")),
+        ]
+    );
+    assert_eq!(
+        annotations("c"),
+        [
+            ("min".to_string(), json!(2)),
+            ("max".to_string(), json!(9)),
+            ("tag".to_string(), json!("a")),
+        ]
+    );
+}
+
+#[test]
+fn test_patch_test_is_strict() {
+    let mut doc = jsona::from_str("{ a: 1, b: 1.0 }").unwrap();
+    assert!(patch(&mut doc, &json!([{"op": "test", "path": "/a", "value": 1}])).is_ok());
+    assert!(patch(
+        &mut doc,
+        &json!([{"op": "test", "path": "/a", "value": 1.0}])
+    )
+    .is_err());
+    assert!(patch(&mut doc, &json!([{"op": "test", "path": "/b", "value": 1}])).is_err());
+}
+
+#[test]
+fn test_merge_patch() {
+    let mut doc = jsona::from_str(BASE).unwrap();
+    merge_patch(
+        &mut doc,
+        &json!({"port": 9090, "tags": null, "db": {"user": "admin", "password": null, "pool": {"size": 4, "x": null}}}),
+    );
+    assert_eq!(
+        Value::from(&doc),
+        json!({"host": "localhost", "port": 9090, "db": {"user": "admin", "pool": {"size": 4}}})
+    );
+    assert_eq!(doc.key("port").unwrap().get_annotations()[0].name, "min");
+    assert_eq!(doc.key("db").unwrap().get_annotations()[0].name, "optional");
+    assert_eq!(doc.get_annotations()[0].name, "save");
+}
@@ -0,0 +1,139 @@
+use jsona::error::ErrorKind;
+use jsona::registry::{AnnotationRegistry, AnnotationSignature, ArgumentSchema, Target};
+
+fn registry() -> AnnotationRegistry {
+    let mut registry = AnnotationRegistry::builtin();
+    registry
+        .register(AnnotationSignature::new("optional", ArgumentSchema::None))
+        .register(
+            AnnotationSignature::new("min", ArgumentSchema::Number).with_targets(&[Target::Scalar]),
+        )
+        .register(AnnotationSignature::new(
+            "enum",
+            ArgumentSchema::Array(Box::new(ArgumentSchema::String)),
+        ))
+        .register(
+            AnnotationSignature::new(
+                "tag",
+                ArgumentSchema::Optional(Box::new(ArgumentSchema::String)),
+            )
+            .repeatable(),
+        );
+    registry
+}
+
+fn validate(input: &str) -> Vec<(ErrorKind, String, usize, usize)> {
+    let doc = jsona::from_str(input).unwrap();
+    registry()
+        .validate(&doc)
+        .into_iter()
+        .map(|err| (err.kind, err.info, err.position.line, err.position.col))
+        .collect()
+}
+
+#[test]
+fn test_validate_ok() {
+    let input = r#"{ @tag @tag("x") @save("root")
+  a: 1, @optional @min(0.5)
+  b: "x", @enum(["x", "y"])
+  c: { @use("root") },
+}"#;
+    assert_eq!(validate(input), vec![]);
+}
+
+#[test]
+fn test_validate_unknown() {
+    use ErrorKind::*;
+    assert_eq!(
+        validate("{ a: 1, @optinal @zzz }"),
+        vec![
+            (
+                UnknownAnnotation,
+                "unknown annotation '@optinal', did you mean '@optional'?".into(),
+                1,
+                10
+            ),
+            (UnknownAnnotation, "unknown annotation '@zzz'".into(), 1, 19),
+        ]
+    );
+}
+
+#[test]
+fn test_validate_arguments() {
+    use ErrorKind::*;
+    let input = r#"[
+  1, @min
+  2, @min("1")
+  3, @enum(["a", 1])
+  4, @optional(true)
+]"#;
+    assert_eq!(
+        validate(input),
+        vec![
+            (
+                InvalidAnnotation,
+                "annotation '@min' expects a number".into(),
+                2,
+                7
+            ),
+            (
+                InvalidAnnotation,
+                "invalid argument of '@min', expected a number, got a string".into(),
+                3,
+                11
+            ),
+            (
+                InvalidAnnotation,
+                "invalid argument of '@enum', expected a string, got an integer".into(),
+                4,
+                18
+            ),
+            (
+                InvalidAnnotation,
+                "annotation '@optional' takes no argument".into(),
+                5,
+                16
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_validate_target_and_duplicate() {
+    let doc = jsona::from_str("{ @min(1)\n  a: 1, @optional @optional\n}").unwrap();
+    let errors = registry().validate(&doc);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(
+        errors[0].info,
+        "annotation '@min' is not allowed on an object, only on scalar"
+    );
+    assert_eq!(errors[1].info, "duplicate annotation '@optional'");
+    assert_eq!(errors[1].position.col, 20);
+    assert_eq!(errors[1].labels[0].message, "first used here");
+    assert_eq!(errors[1].labels[0].position.col, 10);
+}
+
+#[test]
+fn test_argument_schema_display() {
+    let number = || Box::new(ArgumentSchema::Number);
+    assert_eq!(
+        ArgumentSchema::Array(number()).to_string(),
+        "an array of numbers"
+    );
+    assert_eq!(
+        ArgumentSchema::Array(Box::new(ArgumentSchema::Array(number()))).to_string(),
+        "an array of arrays of numbers"
+    );
+    assert_eq!(
+        ArgumentSchema::Array(Box::new(ArgumentSchema::OneOf(vec![
+            ArgumentSchema::String,
+            ArgumentSchema::Object,
+        ])))
+        .to_string(),
+        "an array of strings or objects"
+    );
+    assert_eq!(
+        ArgumentSchema::Optional(Box::new(ArgumentSchema::Integer)).to_string(),
+        "an integer"
+    );
+}
@@ -0,0 +1,69 @@
+use jsona::resolve::{collect_symbols, find_uses, resolve};
+use serde_json::{json, Value};
+
+#[test]
+fn test_resolve() {
+    let mut doc = jsona::from_str(
+        r#"{
+    user: { @save("User")
+        name: "foo",
+        group: {}, @use("Group")
+    },
+    group: { @save("Group")
+        id: 1,
+    },
+    owner: {}, @use("User") @optional
+    members: [
+        {}, @use("User")
+    ],
+}"#,
+    )
+    .unwrap();
+    let (symbols, errors) = collect_symbols(&doc);
+    assert!(errors.is_empty());
+    assert_eq!(symbols["Group"].path, "/group");
+    assert_eq!(symbols["User"].position.line, 2);
+    assert_eq!(find_uses(&doc, "User").len(), 2);
+
+    resolve(&mut doc).unwrap();
+    let user = json!({"name": "foo", "group": {"id": 1}});
+    assert_eq!(Value::from(doc.key("user").unwrap()), user);
+    assert_eq!(Value::from(doc.key("owner").unwrap()), user);
+    assert_eq!(Value::from(doc.pointer("/members/0").unwrap()), user);
+    let owner = doc.key("owner").unwrap();
+    assert!(!owner.has_annotation("save"));
+    assert!(owner.has_annotation("optional"));
+    assert!(doc.key("user").unwrap().has_annotation("save"));
+}
+
+#[test]
+fn test_resolve_errors() {
+    let input = r#"{
+    a: { @save("A")
+        b: {}, @use("B")
+    },
+    b: { @save("B")
+        a: {}, @use("A")
+    },
+    c: {}, @save("A")
+    d: {}, @use("Missing")
+}"#;
+    let mut doc = jsona::from_str(input).unwrap();
+    let errors = resolve(&mut doc).unwrap_err();
+    let errors: Vec<_> = errors
+        .iter()
+        .map(|e| (e.info.as_str(), e.position.line))
+        .collect();
+    assert_eq!(
+        errors,
+        [
+            (
+                "duplicate '@save(\"A\")', first saved at line 2 column 11",
+                8
+            ),
+            ("reference cycle B -> A -> B", 3),
+            ("undefined reference 'Missing'", 9),
+        ]
+    );
+    assert_eq!(doc, jsona::from_str(input).unwrap());
+}
@@ -0,0 +1,115 @@
+use jsona::registry::AnnotationRegistry;
+use jsona::schema::{register, validate, validate_jsona};
+use serde_json::json;
+
+const SCHEMA: &str = r#"{
+    name: "", @pattern("^[a-z]+$")
+    age: 0, @optional @min(0) @max(150)
+    email: "", @format("email")
+    role: "", @enum(["admin", "user"])
+    tags: [""], @max(2)
+    score: 0.0,
+    extra: null, @type("any")
+    ids: [0, ""],
+    nested: {
+        on: false,
+    },
+}"#;
+
+fn violations(instance: serde_json::Value) -> Vec<(String, String, String)> {
+    let schema = jsona::from_str(SCHEMA).unwrap();
+    validate(&schema, &instance)
+        .into_iter()
+        .map(|v| (v.pointer, v.keyword, v.message))
+        .collect()
+}
+
+fn v(pointer: &str, keyword: &str, message: &str) -> (String, String, String) {
+    (pointer.into(), keyword.into(), message.into())
+}
+
+#[test]
+fn test_validate_ok() {
+    let instance = json!({
+        "name": "bob",
+        "email": "bob@example.com",
+        "role": "admin",
+        "tags": ["a"],
+        "score": 3,
+        "extra": [1, {}],
+        "ids": [1, "x"],
+        "nested": { "on": true },
+        "unknown": 1,
+    });
+    assert_eq!(violations(instance), vec![]);
+}
+
+#[test]
+fn test_validate_violations() {
+    let instance = json!({
+        "name": "Bob",
+        "age": -1,
+        "email": "bob",
+        "role": "root",
+        "tags": ["a", 1, "c"],
+        "score": "high",
+        "extra": null,
+        "ids": [true],
+        "nested": {},
+    });
+    assert_eq!(
+        violations(instance),
+        vec![
+            v("/name", "pattern", "does not match pattern '^[a-z]+$'"),
+            v("/age", "min", "value -1 is less than the minimum 0"),
+            v("/email", "format", "is not a valid email"),
+            v(
+                "/role",
+                "enum",
+                "value \"root\" is not one of [\"admin\",\"user\"]"
+            ),
+            v("/tags", "max", "length 3 is greater than the maximum 2"),
+            v("/tags/1", "type", "expected string, got integer"),
+            v("/score", "type", "expected number, got string"),
+            v("/ids/0", "items", "matches none of the 2 element schemas"),
+            v("/nested", "required", "is missing required property 'on'"),
+        ]
+    );
+}
+
+#[test]
+fn test_validate_positions_and_root() {
+    let schema = jsona::from_str(SCHEMA).unwrap();
+    let errors = validate(&schema, &json!({ "name": "x", "age": 200 }));
+    assert_eq!(errors[0].pointer, "/age");
+    assert_eq!((errors[0].position.line, errors[0].position.col), (3, 32));
+    assert_eq!(
+        errors[0].to_string(),
+        "'/age' value 200 is greater than the maximum 150, see schema at line 3 column 32"
+    );
+    assert_eq!(errors[1].pointer, "");
+    assert_eq!(errors[1].keyword, "required");
+    assert_eq!((errors[1].position.line, errors[1].position.col), (4, 5));
+    let errors = validate(&schema, &json!([]));
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "expected object, got array");
+}
+
+#[test]
+fn test_validate_jsona_and_types() {
+    let schema = jsona::from_str(r#"[{ @type(["integer", "null"]) }, { @type("uuid") }]"#).unwrap();
+    let instance = jsona::from_str("[1.0, null, \"x\"]").unwrap();
+    let errors = validate_jsona(&schema, &instance);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].pointer, "/2");
+    let schema = jsona::from_str(r#"{ a: 0, @type("uuid") @min("x") }"#).unwrap();
+    let errors = validate(&schema, &json!({ "a": 1 }));
+    assert_eq!(errors[0].message, "has unknown type 'uuid' in schema");
+    let mut registry = AnnotationRegistry::new();
+    register(&mut registry);
+    let errors = registry.validate(&schema);
+    assert_eq!(
+        errors[0].info,
+        "invalid argument of '@min', expected a number, got a string"
+    );
+}
@@ -0,0 +1,58 @@
+use jsona::include::{load, MemoryResolver};
+use jsona::source::{FileId, SourceMap};
+
+#[test]
+fn test_source_map() {
+    let mut sources = SourceMap::new();
+    let a = sources.add("a.jsona", "{\n  x: 1,\n}");
+    let b = sources.add("b.jsona", "{\r\n  x: ,\r\n}");
+    assert_eq!(a, FileId(1));
+    assert_eq!(sources.find("b.jsona"), Some(b));
+    assert_eq!(sources.add("a.jsona", "{\n  y: 1,\n}"), a);
+
+    let doc = sources.parse(a).unwrap();
+    let position = doc.key("y").unwrap().get_position();
+    assert_eq!(position.file, a);
+    assert_eq!(
+        format!("{:?}", position),
+        "Position { index: 7, line: 2, col: 6, file: FileId(1) }"
+    );
+    assert_eq!(sources.location(position), "a.jsona:2:6");
+    assert_eq!(sources.line(position), Some("  y: 1,"));
+
+    let err = sources.parse(b).unwrap_err();
+    assert_eq!(err.position.file, b);
+    assert_eq!(
+        sources.render(&err),
+        "b.jsona:2:4: unexpected token ':'\n  x: ,"
+    );
+
+    let doc = jsona::from_str("[1]").unwrap();
+    assert!(doc.get_position().file.is_none());
+    assert_eq!(
+        format!("{:?}", doc.get_position()),
+        "Position { index: 0, line: 1, col: 1 }"
+    );
+    assert_eq!(sources.location(doc.get_position()), "1:1");
+}
+
+#[test]
+fn test_composed_positions() {
+    let resolver: MemoryResolver = vec![
+        ("main.jsona", "{\n  user: {}, @include(\"user.jsona\")\n}"),
+        (
+            "user.jsona",
+            "{\n  name: \"foo\", @description(\"name\")\n}",
+        ),
+    ]
+    .into_iter()
+    .collect();
+    let composed = load("main.jsona", &resolver).unwrap();
+    let name = composed.root.pointer("/user/name").unwrap();
+    let anno = name.annotation("description").unwrap();
+    assert_eq!(composed.sources.location(&anno.position), "user.jsona:2:17");
+    assert_eq!(
+        composed.sources.location(composed.root.get_position()),
+        "main.jsona:1:1"
+    );
+}
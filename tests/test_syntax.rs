@@ -0,0 +1,254 @@
+use jsona::syntax::{Annotation, Jsona};
+use serde::Serialize;
+use serde_json::{json, Value};
+
+#[test]
+fn test_from_value() {
+    let value = json!({"a": [1, 2.5, "s", null, true], "b": {"c": u64::MAX}});
+    let node = Jsona::from(value.clone());
+    assert!(node.retrive(&["a", "0"]).unwrap().is_integer());
+    assert!(node.retrive(&["a", "1"]).unwrap().is_float());
+    assert!(node.retrive(&["b", "c"]).unwrap().is_float());
+    assert_eq!(Value::from(node.retrive(&["a"]).unwrap()), value["a"]);
+}
+
+#[test]
+fn test_to_jsona() {
+    #[derive(Serialize)]
+    struct User {
+        name: String,
+        age: u8,
+    }
+    let node = jsona::to_jsona(&User {
+        name: "foo".into(),
+        age: 3,
+    })
+    .unwrap()
+    .with_annotation(Annotation::new("save", json!("User")));
+    assert_eq!(Value::from(&node), json!({"name": "foo", "age": 3}));
+    assert_eq!(node.get_annotations()[0].name, "save");
+    let err = jsona::to_jsona(&[vec![1u64], vec![u64::MAX]]).unwrap_err();
+    assert_eq!(err.info, "integer 18446744073709551615 is out of range");
+    assert!(jsona::to_jsona(&(i64::MAX as u64)).unwrap().is_integer());
+}
+
+#[test]
+fn test_macro() {
+    let name = "foo";
+    let node = jsona::jsona!({ @save("User") @deprecated
+        name: name, @upper
+        "tags": [ @array
+            "a", @upper
+            -1,
+            [],
+        ],
+        (format!("k{}", 1)): { @object({ min: 1 }) },
+        nested: { k: null, @optional },
+    });
+    let annotation_names = |n: &Jsona| {
+        n.get_annotations()
+            .iter()
+            .map(|a| a.name.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(annotation_names(&node), ["save", "deprecated"]);
+    assert_eq!(Value::from(&node.get_annotations()[0].value), json!("User"));
+    assert!(node.get_annotations()[1].value.is_null());
+    assert_eq!(annotation_names(node.key("name").unwrap()), ["upper"]);
+    assert_eq!(annotation_names(node.key("tags").unwrap()), ["array"]);
+    assert_eq!(
+        annotation_names(node.retrive(&["tags", "0"]).unwrap()),
+        ["upper"]
+    );
+    assert_eq!(
+        Value::from(&node.key("k1").unwrap().get_annotations()[0].value),
+        json!({"min": 1})
+    );
+    assert_eq!(
+        annotation_names(node.retrive(&["nested", "k"]).unwrap()),
+        ["optional"]
+    );
+    assert_eq!(
+        Value::from(&node),
+        json!({"name": "foo", "tags": ["a", -1, []], "k1": {}, "nested": {"k": null}})
+    );
+}
+
+#[test]
+fn test_node_at() {
+    use jsona::syntax::{Located, Position};
+    let input = r#"{ @save("x")
+    a: [1, { b: "v" }], @upper
+    c: true,
+}"#;
+    let node = jsona::from_str(input).unwrap();
+    let at = |line, col| {
+        node.node_at(&Position::new(0, line, col))
+            .map(|(path, v)| (path.join("/"), v))
+    };
+    let (path, v) = at(1, 5).unwrap();
+    assert_eq!(path, "");
+    assert!(matches!(v, Located::Annotation(a) if a.name == "save"));
+    let (path, v) = at(2, 5).unwrap();
+    assert_eq!(path, "a");
+    assert!(matches!(v, Located::Key(p) if p.key == "a"));
+    let (path, v) = at(2, 9).unwrap();
+    assert_eq!(path, "a/0");
+    assert!(matches!(v, Located::Node(n) if n.is_integer()));
+    let (path, v) = at(2, 19).unwrap();
+    assert_eq!(path, "a/1/b");
+    assert!(matches!(v, Located::Node(n) if n.is_string()));
+    let (path, v) = at(2, 10).unwrap();
+    assert_eq!(path, "a");
+    assert!(matches!(v, Located::Node(n) if n.is_array()));
+    let (path, v) = at(2, 27).unwrap();
+    assert_eq!(path, "a");
+    assert!(matches!(v, Located::Annotation(a) if a.name == "upper"));
+    let (path, v) = at(3, 1).unwrap();
+    assert_eq!(path, "");
+    assert!(matches!(v, Located::Node(n) if n.is_object()));
+    assert!(at(5, 1).is_none());
+}
+
+#[test]
+fn test_semantic_eq() {
+    use jsona::semantic::SemanticOptions;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    let hash = |n: &Jsona| {
+        let mut hasher = DefaultHasher::new();
+        n.hash(&mut hasher);
+        hasher.finish()
+    };
+    let a = jsona::from_str("{ @save(\"x\") @optional a: 1, b: [true, null] }").unwrap();
+    let b = jsona::from_str("{\n  @optional @save('x')\n  a: 1,\n  b: [true, null],\n}").unwrap();
+    let c = jsona::from_str("{ @optional @save('x') b: [true, null], a: 1 }").unwrap();
+    let d = jsona::from_str("{ b: [true, null], a: 1 }").unwrap();
+    let default = SemanticOptions::default();
+    assert_ne!(a, b);
+    assert!(a.semantic_eq(&b, &default));
+    assert_eq!(hash(&a), hash(&b));
+    assert!(!a.semantic_eq(&c, &default));
+    assert_eq!(hash(&a), hash(&c));
+    let options = SemanticOptions {
+        ignore_key_order: true,
+        ..Default::default()
+    };
+    assert!(a.semantic_eq(&c, &options));
+    assert!(!a.semantic_eq(&d, &options));
+    let options = SemanticOptions {
+        ignore_annotations: true,
+        ignore_key_order: true,
+    };
+    assert!(a.semantic_eq(&d, &options));
+    assert_ne!(hash(&a), hash(&d));
+
+    let options = SemanticOptions {
+        ignore_key_order: true,
+        ..Default::default()
+    };
+    let dup = jsona::from_str("{ a: 1, a: 1 }").unwrap();
+    let other = jsona::from_str("{ a: 1, b: 2 }").unwrap();
+    assert!(!dup.semantic_eq(&other, &options));
+    assert!(!other.semantic_eq(&dup, &options));
+    assert_ne!(hash(&dup), hash(&other));
+    let e = jsona::from_str("{ a: 1, b: 0, a: 2 }").unwrap();
+    let f = jsona::from_str("{ a: 2, a: 1, b: 0 }").unwrap();
+    assert!(e.semantic_eq(&f, &options));
+    assert_eq!(hash(&e), hash(&f));
+}
+
+#[test]
+fn test_key_index() {
+    use jsona::syntax::Property;
+
+    let mut node = jsona::from_str("{ a: 1, b: { c: 2 }, a: 3 }").unwrap();
+    node.build_index();
+    let object = match &mut node {
+        Jsona::Object(object) => object,
+        _ => unreachable!(),
+    };
+    assert!(object.is_indexed());
+    assert_eq!(Value::from(object.get("a").unwrap()), json!(1));
+    assert!(object.insert("d", Jsona::from(json!(4))).is_none());
+    assert_eq!(object.position_of("d"), Some(3));
+    assert!(object.remove("a").is_some());
+    assert_eq!(Value::from(object.get("a").unwrap()), json!(3));
+    assert_eq!(object.position_of("d"), Some(2));
+    object
+        .properties
+        .push(Property::new("e", Jsona::from(json!(5))));
+    assert_eq!(object.position_of("e"), Some(3));
+    assert_eq!(Value::from(node.retrive(&["b", "c"]).unwrap()), json!(2));
+    let fresh = jsona::from_str("{ b: { c: 2 }, a: 3, d: 4, e: 5 }").unwrap();
+    assert_eq!(Value::from(&node), Value::from(&fresh));
+}
+
+#[test]
+fn test_key_index_stale() {
+    let mut node = jsona::from_str("{ a: 1, b: 2 }").unwrap();
+    node.build_index();
+    let object = match &mut node {
+        Jsona::Object(object) => object,
+        _ => unreachable!(),
+    };
+    object.properties.swap(0, 1);
+    assert_eq!(object.position_of("a"), Some(1));
+    assert_eq!(object.position_of("b"), Some(0));
+    object.properties[0].key = "c".into();
+    assert_eq!(object.position_of("c"), Some(0));
+    assert_eq!(object.position_of("b"), None);
+    assert_eq!(Value::from(object.get("c").unwrap()), json!(2));
+}
+
+#[test]
+fn test_key_index_stale_duplicate() {
+    let mut node = jsona::from_str("{ a: 1, b: 2, c: 3 }").unwrap();
+    node.build_index();
+    let object = match &mut node {
+        Jsona::Object(object) => object,
+        _ => unreachable!(),
+    };
+    object.properties[0].key = "c".into();
+    assert_eq!(object.position_of("c"), Some(0));
+    assert_eq!(Value::from(object.get("c").unwrap()), json!(1));
+    object.properties[1].key = "c".into();
+    object.properties[0].key = "a".into();
+    assert_eq!(object.position_of("c"), Some(1));
+}
+
+#[test]
+fn test_annotation_query() {
+    let node = jsona::from_str(
+        r#"{
+    a: 1, @tag("x") @range([1, 10]) @tag("y") @optional
+}"#,
+    )
+    .unwrap();
+    let a = node.key("a").unwrap();
+    assert!(a.has_annotation("optional"));
+    assert!(!a.has_annotation("save"));
+    assert_eq!(Value::from(&a.annotation("tag").unwrap().value), json!("x"));
+    let tags: Vec<_> = a
+        .annotations_named("tag")
+        .map(|v| Value::from(&v.value))
+        .collect();
+    assert_eq!(tags, [json!("x"), json!("y")]);
+    assert_eq!(a.annotation_as::<(u8, u8)>("range").unwrap(), Some((1, 10)));
+    assert_eq!(a.annotation_as::<u8>("missing").unwrap(), None);
+    let err = a.annotation_as::<String>("range").unwrap_err();
+    assert_eq!(err.position.line, 2);
+    assert_eq!(err.position.col, 28);
+    assert!(err.info.starts_with("invalid value of annotation '@range'"));
+    let range = &a.annotation("range").unwrap().value;
+    assert_eq!(range.key("1").unwrap().get_position().col, 32);
+    let err = a.annotation_as::<(u8, String)>("range").unwrap_err();
+    assert_eq!(err.position, *range.key("1").unwrap().get_position());
+    assert_eq!(
+        err.info,
+        "invalid value of annotation '@range', invalid type: integer `10`, expected a string"
+    );
+    let err = a.annotation_as::<(u8,)>("range").unwrap_err();
+    assert_eq!(err.position.col, 28);
+}
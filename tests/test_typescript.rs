@@ -0,0 +1,169 @@
+use jsona::typescript::{to_typescript, to_typescript_source, TypeScriptOptions};
+
+#[test]
+fn test_to_typescript() {
+    let doc = jsona::from_str(
+        r#"{ @description("A user account")
+    id: 0,
+    name: "", @description("Display name")
+    score: 1.5, @optional
+    role: "", @enum(["admin", "user"])
+    note: "", @type(["string", "null"])
+    extra: null, @type("any")
+    tags: [""],
+    mixed: [0, ""],
+    empty: [],
+    address: {
+        street_name: "",
+        geo: { lat: 0.0, lng: 0.0 },
+    },
+    friends: [{ id: 0 }],
+    owner: { @save("User")
+        email: "",
+    },
+    admins: [{ @use("User") }],
+    "content-type": "",
+}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        to_typescript(&doc, &TypeScriptOptions::default()),
+        r#"/** A user account */
+export interface Root {
+    id: number;
+    /** Display name */
+    name: string;
+    score?: number;
+    role: "admin" | "user";
+    note: string | null;
+    extra: unknown;
+    tags: string[];
+    mixed: (number | string)[];
+    empty: unknown[];
+    address: Address;
+    friends: FriendsItem[];
+    owner: User;
+    admins: User[];
+    "content-type": string;
+}
+
+export interface Address {
+    street_name: string;
+    geo: Geo;
+}
+
+export interface Geo {
+    lat: number;
+    lng: number;
+}
+
+export interface FriendsItem {
+    id: number;
+}
+
+export interface User {
+    email: string;
+}
+"#
+    );
+}
+
+#[test]
+fn test_to_typescript_names() {
+    let doc = jsona::from_str(
+        r#"{
+    a: { item: { x: 0 } },
+    b: { item: { y: 0 } },
+    c: { Item: { z: 0 } },
+}"#,
+    )
+    .unwrap();
+    let options = TypeScriptOptions {
+        root: "Doc".into(),
+        indent: 2,
+    };
+    let output = to_typescript(&doc, &options);
+    assert!(output.contains("export interface Doc {\n  a: A;\n  b: B;\n  c: C;\n}\n"));
+    assert!(output.contains("export interface A {\n  item: Item;\n}\n"));
+    assert!(output.contains("export interface B {\n  item: BItem;\n}\n"));
+    assert!(output.contains("export interface C {\n  Item: CItem;\n}\n"));
+    let doc = jsona::from_str(r#"[{ @save("Entry") id: 0 }]"#).unwrap();
+    assert_eq!(
+        to_typescript(&doc, &TypeScriptOptions::default()),
+        "export type Root = Entry[];\n\nexport interface Entry {\n    id: number;\n}\n"
+    );
+}
+
+#[test]
+fn test_to_typescript_comments() {
+    let source = r#"// The config file.
+{
+    // Port to listen on.
+    port: 8080, @description("Must be free")
+    /*
+     * Host names,
+     * without scheme.
+     */
+    hosts: [""],
+    debug: false, // Verbose logging.
+    tls: { // TLS settings.
+        cert: "",
+    },
+}"#;
+    assert_eq!(
+        to_typescript_source(source, &TypeScriptOptions::default()).unwrap(),
+        r#"/** The config file. */
+export interface Root {
+    /**
+     * Port to listen on.
+     * Must be free
+     */
+    port: number;
+    /**
+     * Host names,
+     * without scheme.
+     */
+    hosts: string[];
+    /** Verbose logging. */
+    debug: boolean;
+    /** TLS settings. */
+    tls: Tls;
+}
+
+export interface Tls {
+    cert: string;
+}
+"#
+    );
+    assert!(to_typescript_source("{", &TypeScriptOptions::default()).is_err());
+}
+
+#[test]
+fn test_to_typescript_root_name() {
+    let options = TypeScriptOptions::default();
+    let doc = jsona::from_str(r#"{ @save("Config") port: 0, next: { @use("Config") } }"#).unwrap();
+    assert_eq!(
+        to_typescript(&doc, &options),
+        "export interface Config {\n    port: number;\n    next: Config;\n}\n"
+    );
+
+    let doc = jsona::from_str(r#"{ a: { @save("Root") b: 0 }, c: { @use("Root") } }"#).unwrap();
+    assert_eq!(
+        to_typescript(&doc, &options),
+        r#"export interface Root2 {
+    a: Root;
+    c: Root;
+}
+
+export interface Root {
+    b: number;
+}
+"#
+    );
+
+    let doc = jsona::from_str(r#"{ a: { @use("Nope") }, b: [{ @use("Nope") }] }"#).unwrap();
+    assert_eq!(
+        to_typescript(&doc, &options),
+        "export interface Root {\n    a: unknown;\n    b: unknown[];\n}\n"
+    );
+}